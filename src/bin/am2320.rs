@@ -1,7 +1,7 @@
-use std::{thread::sleep, time::Duration};
+use std::{fmt::Write, thread::sleep, time::Duration};
 
-use embedded_toolbox_rs::pcd8544::{Pcd8544, graphics::DisplayBuffer};
-use rppal::{i2c::I2c, gpio::Gpio, spi::{Spi, Bus, SlaveSelect, Mode}};
+use embedded_toolbox_rs::pcd8544::{Pcd8544, console::TextConsole, graphics::DisplayBuffer};
+use rppal::{i2c::I2c, gpio::Gpio, hal::Delay, spi::{Spi, Bus, SlaveSelect, Mode}};
 
 fn main() {
     let spi = Spi::new(
@@ -11,8 +11,6 @@ fn main() {
         Mode::Mode0
     ).unwrap();
     
-    let mut data = [0u8; 504];
-
     let rst_pin = Gpio::new().unwrap().get(13).unwrap().into_output();
     let ce_pin = Gpio::new().unwrap().get(16).unwrap().into_output();
     let dc_pin = Gpio::new().unwrap().get(26).unwrap().into_output();
@@ -27,10 +25,9 @@ fn main() {
         rst_pin,
     ).unwrap();
 
-    nokia.init().unwrap();
-    nokia.lcd_data(&mut data);
-    nokia.init();
-    let mut display = DisplayBuffer { data };
+    nokia.init(&mut Delay::new()).unwrap();
+    let mut display = DisplayBuffer::new();
+    let mut console = TextConsole::new(&mut display);
 
     let mut i2c = I2c::new().unwrap();
     i2c.set_slave_address(0x5c).unwrap();
@@ -48,9 +45,13 @@ fn main() {
         let t = i16::from_be_bytes(buffer[4..6].try_into().unwrap());
         let h = f64::from(h) / 10.0;
         let t = f64::from(t) / 10.0;
-        display.text_mode_put_text(format!("R. Hum. {:3.1}%", h).as_str(), 0, 0);
-        display.text_mode_put_text(format!("Temp.   {:3.1}C", t).as_str(), 0, 1);
-        nokia.lcd_data(&mut display.data);
+        console.set_cursor(0, 0);
+        // Clear the rest of each line, a shorter value would leave old digits behind
+        write!(console, "R. Hum. {:3.1}%", h).unwrap();
+        console.clear_to_end_of_line();
+        write!(console, "\nTemp.   {:3.1}C", t).unwrap();
+        console.clear_to_end_of_line();
+        nokia.flush(console.buffer_mut()).unwrap();
         println!("{} {}", t, h);
     }
 }
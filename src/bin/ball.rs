@@ -1,36 +1,13 @@
 use std::{thread, time::Duration};
-use std::borrow::BorrowMut;
 use embedded_toolbox_rs::pcd8544::Pcd8544;
+use embedded_toolbox_rs::pcd8544::draw::Color;
+use embedded_toolbox_rs::pcd8544::graphics::DisplayBuffer;
 use rppal::spi::{Spi, Bus, SlaveSelect, Mode};
 use rppal::gpio::Gpio;
+use rppal::hal::Delay;
 
 use rppal::i2c::I2c;
 
-fn set_pixel(data: &mut [u8; 504], x: usize, y: usize, state: bool) {
-    let pos = (y / 8, y % 8);
-    let cur = data[pos.0 * 84 + x].borrow_mut();
-    if state {
-        *cur = *cur | 1u8 << pos.1;
-    } else {
-        *cur = *cur & (!(1u8 << pos.1));
-    }
-}
-
-fn draw_square(
-    data: &mut [u8; 504],
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
-    fill: bool,
-) {
-    for cur_y in y..y + height {
-        for cur_x in x..x + width {
-            set_pixel(data, cur_x, cur_y, fill);
-        }
-    }
-}
-
 fn main() {
     let mut i2c = I2c::new().unwrap();
     i2c.set_slave_address(0x1d).unwrap();
@@ -67,9 +44,9 @@ fn main() {
     let mut x: f64 = 42.0;
     let mut y: f64 = 24.0;
 
-    let mut data = [0u8; 504];
+    let mut display = DisplayBuffer::new();
 
-    nokia.init().unwrap();
+    nokia.init(&mut Delay::new()).unwrap();
 
     loop {
         i2c.block_read(0x05 | 0x80, &mut temp).unwrap();
@@ -85,7 +62,7 @@ fn main() {
             (f64::from(i16::from_le_bytes(acc_z)) * 2.0) / 32678.0,
         );
 
-        draw_square(&mut data, x as usize, y as usize, 4, 4, false);
+        display.fill_rect(x as i32, y as i32, 4, 4, Color::Off);
         let x_acc = (f64::from(i16::from_le_bytes(acc_x)) * 2.0) / 32678.0;
         let z_acc = (f64::from(i16::from_le_bytes(acc_z)) * 2.0) / 32678.0;
 
@@ -95,8 +72,8 @@ fn main() {
         x = x.clamp(0.0, 83.0);
         y = y.clamp(0.0, 47.0);
 
-        draw_square(&mut data, x as usize, y as usize, 4, 4, true);
-        nokia.lcd_data(&mut data);
+        display.fill_rect(x as i32, y as i32, 4, 4, Color::On);
+        nokia.flush(&mut display).unwrap();
 
         thread::sleep(Duration::from_millis(100));
     }
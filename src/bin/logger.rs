@@ -2,7 +2,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use embedded_sdmmc::{BlockDevice, TimeSource};
-use embedded_toolbox_rs::lsm303d::{AccelerometerConfiguration, MagnetometerConfiguration, LSM303D, MagnetometerDataRate, MagneticSensorMode, MagnetometerFullScale, AccelerationDataRate, AccelerationFullScale, InternalTemperatureConfiguration, Measurements};
+use embedded_toolbox_rs::lsm303d::{AccelerometerConfiguration, MagnetometerConfiguration, LSM303D, MagnetometerDataRate, MagneticSensorMode, MagnetometerFullScale, MagnetometerResolution, AccelerationDataRate, AccelerationFullScale, AccelerationAntiAliasBandwidth, InternalTemperatureConfiguration, Measurements};
 use rppal::gpio::Gpio;
 use rppal::i2c::I2c;
 use rppal::spi::{Spi, Bus, SlaveSelect, Mode};
@@ -49,6 +49,8 @@ fn main() {
         data_rate: MagnetometerDataRate::Hz50,
         mode: MagneticSensorMode::ContinuousConversion,
         scale: MagnetometerFullScale::Mag2Gauss,
+        resolution: MagnetometerResolution::High,
+        low_power: false,
     }).unwrap();
     lsm303d.configure_accelerometer(AccelerometerConfiguration
          {
@@ -57,6 +59,8 @@ fn main() {
         axis_z: true,
         data_rate: AccelerationDataRate::Hz50,
         scale: AccelerationFullScale::Acc2G,
+        bandwidth: AccelerationAntiAliasBandwidth::Hz773,
+        high_pass_filter: None,
     }).unwrap();
     lsm303d.configure_internal_temperature(
         InternalTemperatureConfiguration { active: true }
@@ -1,6 +1,6 @@
 use std::{thread, time::Duration};
 
-use embedded_toolbox_rs::lsm303d::{LSM303D, MagnetometerConfiguration, MagnetometerDataRate, MagneticSensorMode, MagnetometerFullScale, AccelerometerConfiguration, AccelerationDataRate, AccelerationFullScale, InternalTemperatureConfiguration, Register};
+use embedded_toolbox_rs::lsm303d::{LSM303D, MagnetometerConfiguration, MagnetometerDataRate, MagneticSensorMode, MagnetometerFullScale, MagnetometerResolution, AccelerometerConfiguration, AccelerationDataRate, AccelerationFullScale, AccelerationAntiAliasBandwidth, InternalTemperatureConfiguration, Register};
 use rppal::i2c::I2c;
 
 fn main() {
@@ -13,6 +13,8 @@ fn main() {
         data_rate: MagnetometerDataRate::Hz50,
         mode: MagneticSensorMode::ContinuousConversion,
         scale: MagnetometerFullScale::Mag2Gauss,
+        resolution: MagnetometerResolution::High,
+        low_power: false,
     }).unwrap();
     lsm303d.configure_accelerometer(AccelerometerConfiguration {
         axis_x: true,
@@ -20,6 +22,8 @@ fn main() {
         axis_z: true,
         data_rate: AccelerationDataRate::Hz50,
         scale: AccelerationFullScale::Acc2G,
+        bandwidth: AccelerationAntiAliasBandwidth::Hz773,
+        high_pass_filter: None,
     }).unwrap();
     lsm303d.configure_internal_temperature(
         InternalTemperatureConfiguration { active: true }
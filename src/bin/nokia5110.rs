@@ -2,34 +2,9 @@ use embedded_toolbox_rs::pcd8544::Pcd8544;
 use embedded_toolbox_rs::pcd8544::graphics::DisplayBuffer;
 use rppal::spi::{Spi, Bus, SlaveSelect, Mode};
 use rppal::gpio::{Gpio, OutputPin};
-use std::borrow::BorrowMut;
+use rppal::hal::Delay;
 use std::{thread, time::Duration};
 
-fn set_pixel(data: &mut [u8; 504], x: usize, y: usize, state: bool) {
-    let pos = (y / 8, y % 8);
-    let cur = data[pos.0 * 84 + x].borrow_mut();
-    if state {
-        *cur = *cur | 1u8 << pos.1;
-    } else {
-        *cur = *cur & (!(1u8 << pos.1));
-    }
-}
-
-fn draw_square(
-    data: &mut [u8; 504],
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
-    fill: bool,
-) {
-    for cur_y in y..y + height {
-        for cur_x in x..x + width {
-            set_pixel(data, cur_x, cur_y, fill);
-        }
-    }
-}
-
 fn main() {
     let mut spi = Spi::new(
         Bus::Spi1,
@@ -51,7 +26,7 @@ fn main() {
         rst_pin,
     ).unwrap();
 
-    nokia.init().unwrap();
+    nokia.init(&mut Delay::new()).unwrap();
     let mut data = [0u8; 504];
 
     // let mut idx = 0u8;
@@ -60,12 +35,11 @@ fn main() {
     //     idx = idx.wrapping_add(8);
     // }
 
-    nokia.lcd_data(&mut data);
     let mut x: i8 = 0;
     let mut y: i8 = 0;
     let mut direction_x: i8 = 1;
     let mut direction_y: i8 = 1;
-    let mut display = DisplayBuffer { data };
+    let mut display = DisplayBuffer::from_data(data);
     loop {
             x = x.wrapping_add(1);
             y = x ^ y;
@@ -73,7 +47,7 @@ fn main() {
             display.text_mode_put_text(format!("Acc y: {:4}", y).as_str(), 0, 1);
             display.text_mode_put_text(format!("Acc z: {:4}", x.overflowing_mul(y).0).as_str(), 0, 2);
             display.text_mode_put_text(format!("Acc a: {:4}", x.overflowing_sub(y).0).as_str(), 0, 3);
-            nokia.lcd_data(&mut display.data).unwrap();
+            nokia.flush(&mut display).unwrap();
             thread::sleep(Duration::from_millis(50));
         }
 }
\ No newline at end of file
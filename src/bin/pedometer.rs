@@ -0,0 +1,53 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use embedded_toolbox_rs::lsm303d::AccelerometerMeasurements;
+use embedded_toolbox_rs::lsm303d::pedometer::Pedometer;
+
+// Replays a trace recorded by the logger (timestamp in ms,acc x,y,z,mag x,y,z per line)
+// through the pedometer and prints every detected step. The logger polls at its own
+// pace, so the sample period is taken from the timestamps unless a rate is given.
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args.next().expect("usage: pedometer <trace.csv> [sample rate in Hz]");
+    let sample_rate: Option<f64> = args.next().map(|rate| rate.parse().expect("invalid sample rate"));
+
+    let file = File::open(path).unwrap();
+    let mut samples = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let (Ok(timestamp), Ok(x), Ok(y), Ok(z)) = (fields[0].parse::<u64>(), fields[1].parse(), fields[2].parse(), fields[3].parse()) else {
+            continue;
+        };
+        samples.push((timestamp, AccelerometerMeasurements { x, y, z }));
+    }
+
+    let sample_period = match (sample_rate, samples.first(), samples.last()) {
+        (Some(rate), _, _) => 1.0 / rate,
+        (None, Some((first, _)), Some((last, _))) if samples.len() > 1 => {
+            (last - first) as f64 / 1000.0 / (samples.len() - 1) as f64
+        }
+        _ => panic!("trace needs at least two samples or an explicit sample rate"),
+    };
+    println!("sample period: {:.1} ms", sample_period * 1000.0);
+
+    let mut pedometer = Pedometer::new(sample_period).unwrap();
+    for (timestamp, measurements) in &samples {
+        if pedometer.update(measurements) {
+            println!(
+                "{:>8} ms step {:4}  cadence {:5.1}/min  {:?}",
+                timestamp,
+                pedometer.step_count(),
+                pedometer.cadence(),
+                pedometer.activity(),
+            );
+        }
+    }
+
+    println!("total steps: {}", pedometer.step_count());
+}
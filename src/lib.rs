@@ -3,6 +3,8 @@
 pub mod pcd8544;
 pub mod am2320;
 pub mod dht11;
+pub mod lsm303;
 pub mod lsm303d;
+pub mod register;
 
 pub mod sensors;
\ No newline at end of file
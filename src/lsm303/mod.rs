@@ -0,0 +1,418 @@
+use embedded_hal::blocking::i2c::{WriteRead, Write};
+
+use crate::lsm303d::{
+    LSM303D,
+    AccelerationDataRate,
+    AccelerationFullScale,
+    AccelerometerConfiguration,
+    AccelerometerMeasurements,
+    InternalTemperatureConfiguration,
+    MagneticSensorMode,
+    MagnetometerConfiguration,
+    MagnetometerDataRate,
+    MagnetometerFullScale,
+    MagnetometerMeasurements,
+    Measurements,
+};
+use crate::lsm303d::transport::{I2cTransport, SlaveAddress};
+use crate::register::{Register as _, RegisterAccess};
+
+mod registers;
+
+use registers::{CfgRegAM, CfgRegCM, CraRegM, CrbRegM, CtrlReg1A, CtrlReg4A, MrRegM, TempCfgRegA};
+
+// LSM303DLHC and LSM303AGR put accelerometer and magnetometer on separate addresses.
+static ACCELEROMETER_ADDRESS: u8 = 0x19;
+static MAGNETOMETER_ADDRESS: u8 = 0x1E;
+
+static LSM303D_WHO_AM_I: u8 = 0x49;
+static LSM303AGR_WHO_AM_I_A: u8 = 0x33;
+static LSM303AGR_WHO_AM_I_M: u8 = 0x40;
+static LSM303DLHC_IRA_REG_M: u8 = 0x48;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Variant {
+    LSM303D,
+    LSM303DLHC,
+    LSM303AGR,
+}
+
+enum Register {
+    // Accelerometer, both variants
+    WhoAmIA   = 0x0F,
+    OutXLA    = 0x28,
+    OutTempLA = 0x0C, // LSM303AGR only
+
+    // LSM303DLHC magnetometer
+    OutXHM    = 0x03,
+    IraRegM   = 0x0A,
+    TempOutHM = 0x31,
+
+    // LSM303AGR magnetometer
+    WhoAmIM   = 0x4F,
+    OutXLRegM = 0x68,
+}
+
+/// Driver for any member of the LSM303 family, chosen by probing the bus.
+pub struct LSM303<I2C> {
+    inner: Inner<I2C>,
+}
+
+enum Inner<I2C> {
+    D(LSM303D<I2cTransport<I2C>>),
+    Split(Split<I2C>),
+}
+
+/// LSM303DLHC and LSM303AGR, which share most of the accelerometer register map.
+struct Split<I2C> {
+    i2c: I2C,
+    variant: Variant,
+    // g per LSB of the 12 bit high resolution output
+    acc_sensitivity: f64,
+    // LSB per gauss, the LSM303DLHC has a different gain on the z axis
+    mag_gain_xy: f64,
+    mag_gain_z: f64,
+}
+
+impl<I2C> LSM303<I2C>
+where I2C: WriteRead + Write {
+    /// Probes the known addresses and identification registers and returns a driver
+    /// for the first variant found.
+    pub fn detect(mut i2c: I2C) -> Result<Self, ()> {
+        for address in [SlaveAddress::SA0High, SlaveAddress::SA0Low] {
+            if read(&mut i2c, address as u8, Register::WhoAmIA as u8) == Ok(LSM303D_WHO_AM_I) {
+                return Ok(Self {
+                    inner: Inner::D(LSM303D::new_with_address(i2c, address)),
+                });
+            }
+        }
+
+        let variant = if read(&mut i2c, MAGNETOMETER_ADDRESS, Register::WhoAmIM as u8) == Ok(LSM303AGR_WHO_AM_I_M)
+            && read(&mut i2c, ACCELEROMETER_ADDRESS, Register::WhoAmIA as u8) == Ok(LSM303AGR_WHO_AM_I_A)
+        {
+            Variant::LSM303AGR
+        } else if read(&mut i2c, MAGNETOMETER_ADDRESS, Register::IraRegM as u8) == Ok(LSM303DLHC_IRA_REG_M) {
+            Variant::LSM303DLHC
+        } else {
+            return Err(());
+        };
+
+        // Power-on scaling: ±2 g and, on the LSM303DLHC, ±1.3 gauss
+        let (mag_gain_xy, mag_gain_z) = match variant {
+            Variant::LSM303AGR => (1.0 / 0.0015, 1.0 / 0.0015),
+            _ => (1100.0, 980.0),
+        };
+
+        Ok(Self {
+            inner: Inner::Split(Split {
+                i2c,
+                variant,
+                acc_sensitivity: acceleration_sensitivity(variant, 0b0000_0000),
+                mag_gain_xy,
+                mag_gain_z,
+            }),
+        })
+    }
+
+    pub fn variant(&self) -> Variant {
+        match &self.inner {
+            Inner::D(_) => Variant::LSM303D,
+            Inner::Split(split) => split.variant,
+        }
+    }
+
+    pub fn check_connection(&mut self) -> Result<bool, ()> {
+        match &mut self.inner {
+            Inner::D(lsm303d) => lsm303d.check_connection(),
+            Inner::Split(split) => split.check_connection(),
+        }
+    }
+
+    pub fn configure_internal_temperature(&mut self, configuration: InternalTemperatureConfiguration) -> Result<(), ()> {
+        match &mut self.inner {
+            Inner::D(lsm303d) => lsm303d.configure_internal_temperature(configuration),
+            Inner::Split(split) => split.configure_internal_temperature(configuration),
+        }
+    }
+
+    /// On the LSM303DLHC and LSM303AGR the closest supported data rate and full scale at
+    /// or above the requested one are used; bandwidth and high-pass filter are ignored.
+    pub fn configure_accelerometer(&mut self, configuration: AccelerometerConfiguration) -> Result<(), ()> {
+        match &mut self.inner {
+            Inner::D(lsm303d) => lsm303d.configure_accelerometer(configuration),
+            Inner::Split(split) => split.configure_accelerometer(configuration),
+        }
+    }
+
+    /// On the LSM303DLHC and LSM303AGR the closest supported data rate and full scale at
+    /// or above the requested one are used. `Mag12Gauss` returns `Err` on the LSM303DLHC,
+    /// which tops out at ±8.1 gauss. The LSM303AGR always measures ±50 gauss, resolution
+    /// only applies to the LSM303D and low power is ignored by the LSM303DLHC.
+    pub fn configure_magnetometer(&mut self, configuration: MagnetometerConfiguration) -> Result<(), ()> {
+        match &mut self.inner {
+            Inner::D(lsm303d) => lsm303d.configure_magnetometer(configuration),
+            Inner::Split(split) => split.configure_magnetometer(configuration),
+        }
+    }
+
+    /// Temperature is reported in the LSM303D scale of 8 LSB per °C for every variant.
+    pub fn read_measurements(&mut self) -> Result<Measurements, ()> {
+        match &mut self.inner {
+            Inner::D(lsm303d) => lsm303d.read_measurements(),
+            Inner::Split(split) => split.read_measurements(),
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        match self.inner {
+            Inner::D(lsm303d) => lsm303d.release().release(),
+            Inner::Split(split) => split.i2c,
+        }
+    }
+}
+
+impl<I2C> Split<I2C>
+where I2C: WriteRead + Write {
+    fn check_connection(&mut self) -> Result<bool, ()> {
+        Ok(match self.variant {
+            Variant::LSM303AGR => read(&mut self.i2c, MAGNETOMETER_ADDRESS, Register::WhoAmIM as u8)? == LSM303AGR_WHO_AM_I_M,
+            _ => read(&mut self.i2c, MAGNETOMETER_ADDRESS, Register::IraRegM as u8)? == LSM303DLHC_IRA_REG_M,
+        })
+    }
+
+    fn configure_internal_temperature(&mut self, configuration: InternalTemperatureConfiguration) -> Result<(), ()> {
+        match self.variant {
+            Variant::LSM303AGR => {
+                self.accelerometer().modify(|temp_cfg: &mut TempCfgRegA| {
+                    temp_cfg
+                        .set_temperature_enable_1(configuration.active)
+                        .set_temperature_enable_0(configuration.active);
+                })?;
+            }
+            _ => {
+                self.magnetometer().modify(|cra: &mut CraRegM| {
+                    cra.set_temperature_enable(configuration.active);
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn configure_accelerometer(&mut self, configuration: AccelerometerConfiguration) -> Result<(), ()> {
+        let data_rate: u8 = match configuration.data_rate {
+            AccelerationDataRate::PowerOff => 0b0000_0000,
+            AccelerationDataRate::Hz3_125 | AccelerationDataRate::Hz6_25 => 0b0010_0000, // 10 Hz
+            AccelerationDataRate::Hz12_5 | AccelerationDataRate::Hz25 => 0b0011_0000,
+            AccelerationDataRate::Hz50 => 0b0100_0000,
+            AccelerationDataRate::Hz100 => 0b0101_0000,
+            AccelerationDataRate::Hz200 => 0b0110_0000,
+            AccelerationDataRate::Hz400 => 0b0111_0000,
+            AccelerationDataRate::Hz800 | AccelerationDataRate::Hz1600 => 0b1001_0000, // 1344 Hz
+        };
+
+        let mut ctrl1 = CtrlReg1A::reset();
+        ctrl1
+            .set_data_rate(data_rate)
+            .set_axis_x(configuration.axis_x)
+            .set_axis_y(configuration.axis_y)
+            .set_axis_z(configuration.axis_z);
+        self.accelerometer().write(ctrl1)?;
+
+        let scale: u8 = match configuration.scale {
+            AccelerationFullScale::Acc2G => 0b0000_0000,
+            AccelerationFullScale::Acc4G => 0b0001_0000,
+            AccelerationFullScale::Acc6G | AccelerationFullScale::Acc8G => 0b0010_0000,
+            AccelerationFullScale::Acc16G => 0b0011_0000,
+        };
+
+        let mut ctrl4 = CtrlReg4A::reset();
+        ctrl4
+            .set_block_data_update(true)
+            .set_high_resolution(true)
+            .set_scale(scale);
+        self.accelerometer().write(ctrl4)?;
+        self.acc_sensitivity = acceleration_sensitivity(self.variant, scale);
+
+        Ok(())
+    }
+
+    fn configure_magnetometer(&mut self, configuration: MagnetometerConfiguration) -> Result<(), ()> {
+        let mode: u8 = match configuration.mode {
+            MagneticSensorMode::ContinuousConversion => 0b00,
+            MagneticSensorMode::SingleConversion => 0b01,
+            MagneticSensorMode::PowerDown => 0b11,
+        };
+
+        match self.variant {
+            Variant::LSM303AGR => {
+                let data_rate: u8 = match configuration.data_rate {
+                    MagnetometerDataRate::Hz3_125 | MagnetometerDataRate::Hz6_25 => 0b0000_0000, // 10 Hz
+                    MagnetometerDataRate::Hz12_5 => 0b0000_0100, // 20 Hz
+                    MagnetometerDataRate::Hz25 | MagnetometerDataRate::Hz50 => 0b0000_1000,
+                    MagnetometerDataRate::Hz100 => 0b0000_1100,
+                };
+                let mut cfg_a = CfgRegAM::reset();
+                cfg_a
+                    // Required by the datasheet
+                    .set_temperature_compensation(true)
+                    .set_low_power(configuration.low_power)
+                    .set_data_rate(data_rate)
+                    .set_mode(mode);
+                self.magnetometer().write(cfg_a)?;
+
+                let mut cfg_c = CfgRegCM::reset();
+                cfg_c.set_block_data_update(true);
+                self.magnetometer().write(cfg_c)?;
+
+                // Fixed 1.5 mgauss per LSB
+                self.mag_gain_xy = 1.0 / 0.0015;
+                self.mag_gain_z = 1.0 / 0.0015;
+            }
+            _ => {
+                // Checked first, so an unsupported range leaves the chip untouched
+                let (gain, gain_xy, gain_z) = match configuration.scale {
+                    MagnetometerFullScale::Mag2Gauss => (0b0110_0000, 670.0, 600.0), // ±2.5 gauss
+                    MagnetometerFullScale::Mag4Gauss => (0b1000_0000, 450.0, 400.0),
+                    MagnetometerFullScale::Mag8Gauss => (0b1110_0000, 230.0, 205.0), // ±8.1 gauss
+                    MagnetometerFullScale::Mag12Gauss => return Err(()),
+                };
+                let data_rate: u8 = match configuration.data_rate {
+                    MagnetometerDataRate::Hz3_125 | MagnetometerDataRate::Hz6_25 => 0b0000_1100, // 7.5 Hz
+                    MagnetometerDataRate::Hz12_5 => 0b0001_0000, // 15 Hz
+                    MagnetometerDataRate::Hz25 => 0b0001_0100, // 30 Hz
+                    MagnetometerDataRate::Hz50 => 0b0001_1000, // 75 Hz
+                    MagnetometerDataRate::Hz100 => 0b0001_1100, // 220 Hz
+                };
+                // Keeps the temperature sensor state
+                self.magnetometer().modify(|cra: &mut CraRegM| {
+                    cra.set_data_rate(data_rate);
+                })?;
+
+                let mut crb = CrbRegM::reset();
+                crb.set_gain(gain);
+                self.magnetometer().write(crb)?;
+                let mut mr = MrRegM::reset();
+                mr.set_mode(mode);
+                self.magnetometer().write(mr)?;
+
+                self.mag_gain_xy = gain_xy;
+                self.mag_gain_z = gain_z;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn accelerometer(&mut self) -> Device<'_, I2C> {
+        Device { i2c: &mut self.i2c, address: ACCELEROMETER_ADDRESS }
+    }
+
+    fn magnetometer(&mut self) -> Device<'_, I2C> {
+        Device { i2c: &mut self.i2c, address: MAGNETOMETER_ADDRESS }
+    }
+
+    fn read_measurements(&mut self) -> Result<Measurements, ()> {
+        let mut acc = [0u8; 6];
+        // MSB of the sub-address enables auto-increment on the accelerometer
+        self.i2c.write_read(ACCELEROMETER_ADDRESS, &[Register::OutXLA as u8 | 0x80], &mut acc).map_err(|_| ())?;
+        // 12 bit, left aligned
+        let acc_axis = |index: usize| {
+            f64::from(i16::from_le_bytes([acc[index], acc[index + 1]]) >> 4) * self.acc_sensitivity
+        };
+
+        let mut mag = [0u8; 6];
+        let (magnetometer, temperature) = match self.variant {
+            Variant::LSM303AGR => {
+                self.i2c.write_read(MAGNETOMETER_ADDRESS, &[Register::OutXLRegM as u8], &mut mag).map_err(|_| ())?;
+                let mag_axis = |index: usize| f64::from(i16::from_le_bytes([mag[index], mag[index + 1]]));
+
+                let mut temperature = [0u8; 2];
+                self.i2c.write_read(ACCELEROMETER_ADDRESS, &[Register::OutTempLA as u8 | 0x80], &mut temperature).map_err(|_| ())?;
+
+                (
+                    MagnetometerMeasurements {
+                        x: mag_axis(0) / self.mag_gain_xy,
+                        y: mag_axis(2) / self.mag_gain_xy,
+                        z: mag_axis(4) / self.mag_gain_z,
+                    },
+                    // 1 LSB per °C in the high byte
+                    f64::from(temperature[1] as i8) * 8.0,
+                )
+            }
+            _ => {
+                // Big endian, in X, Z, Y order
+                self.i2c.write_read(MAGNETOMETER_ADDRESS, &[Register::OutXHM as u8], &mut mag).map_err(|_| ())?;
+                let mag_axis = |index: usize| f64::from(i16::from_be_bytes([mag[index], mag[index + 1]]));
+
+                let mut temperature = [0u8; 2];
+                self.i2c.write_read(MAGNETOMETER_ADDRESS, &[Register::TempOutHM as u8], &mut temperature).map_err(|_| ())?;
+
+                (
+                    MagnetometerMeasurements {
+                        x: mag_axis(0) / self.mag_gain_xy,
+                        y: mag_axis(4) / self.mag_gain_xy,
+                        z: mag_axis(2) / self.mag_gain_z,
+                    },
+                    // 12 bit, left aligned, 8 LSB per °C
+                    f64::from(i16::from_be_bytes(temperature) >> 4),
+                )
+            }
+        };
+
+        Ok(Measurements {
+            temperature,
+            accelerometer: AccelerometerMeasurements {
+                x: acc_axis(0),
+                y: acc_axis(2),
+                z: acc_axis(4),
+            },
+            magnetometer,
+        })
+    }
+}
+
+/// Accelerometer or magnetometer of the split variants, for typed register access.
+struct Device<'a, I2C> {
+    i2c: &'a mut I2C,
+    address: u8,
+}
+
+impl<I2C> RegisterAccess for Device<'_, I2C>
+where I2C: WriteRead + Write {
+    fn read_raw(&mut self, address: u8) -> Result<u8, ()> {
+        read(self.i2c, self.address, address)
+    }
+
+    fn write_raw(&mut self, address: u8, value: u8) -> Result<(), ()> {
+        write(self.i2c, self.address, address, value)
+    }
+}
+
+/// Sensitivity in g per LSB in high resolution mode for the CTRL_REG4_A full scale bits.
+/// Neither chip is exactly FS / 2048, most notably at ±16 g.
+fn acceleration_sensitivity(variant: Variant, scale: u8) -> f64 {
+    let milli_g = match (variant, scale) {
+        (Variant::LSM303AGR, 0b0000_0000) => 0.98,
+        (Variant::LSM303AGR, 0b0001_0000) => 1.95,
+        (Variant::LSM303AGR, 0b0010_0000) => 3.9,
+        (Variant::LSM303AGR, _) => 11.72,
+        (_, 0b0000_0000) => 1.0,
+        (_, 0b0001_0000) => 2.0,
+        (_, 0b0010_0000) => 4.0,
+        (_, _) => 12.0,
+    };
+
+    milli_g / 1000.0
+}
+
+fn read<I2C: WriteRead>(i2c: &mut I2C, address: u8, register: u8) -> Result<u8, ()> {
+    let mut buffer = [0u8; 1];
+    i2c.write_read(address, &[register], &mut buffer).map_err(|_| ())?;
+
+    Ok(buffer[0])
+}
+
+fn write<I2C: Write>(i2c: &mut I2C, address: u8, register: u8, value: u8) -> Result<(), ()> {
+    i2c.write(address, &[register, value]).map_err(|_| ())
+}
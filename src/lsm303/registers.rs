@@ -0,0 +1,77 @@
+use crate::register::register;
+
+// Multi-bit fields hold the datasheet codes in register position, the variants differ
+// too much to share the LSM303D enums.
+
+register! {
+    TempCfgRegA = 0x1F, reset 0x00 {
+        // LSM303AGR only, both bits have to be set to enable the sensor
+        flag temperature_enable_1, set_temperature_enable_1: 7;
+        flag temperature_enable_0, set_temperature_enable_0: 6;
+    }
+}
+
+register! {
+    CtrlReg1A = 0x20, reset 0x07 {
+        flag low_power, set_low_power: 3;
+        flag axis_z, set_axis_z: 2;
+        flag axis_y, set_axis_y: 1;
+        flag axis_x, set_axis_x: 0;
+        field data_rate, set_data_rate: u8 = 0b1111_0000;
+    }
+}
+
+register! {
+    CtrlReg4A = 0x23, reset 0x00 {
+        flag block_data_update, set_block_data_update: 7;
+        flag big_endian, set_big_endian: 6;
+        flag high_resolution, set_high_resolution: 3;
+        field scale, set_scale: u8 = 0b0011_0000;
+    }
+}
+
+register! {
+    /// LSM303DLHC magnetometer.
+    CraRegM = 0x00, reset 0x10 {
+        flag temperature_enable, set_temperature_enable: 7;
+        field data_rate, set_data_rate: u8 = 0b0001_1100;
+    }
+}
+
+register! {
+    /// LSM303DLHC magnetometer.
+    CrbRegM = 0x01, reset 0x20 {
+        field gain, set_gain: u8 = 0b1110_0000;
+    }
+}
+
+register! {
+    /// LSM303DLHC magnetometer.
+    MrRegM = 0x02, reset 0x03 {
+        field mode, set_mode: u8 = 0b0000_0011;
+    }
+}
+
+register! {
+    /// LSM303AGR magnetometer.
+    CfgRegAM = 0x60, reset 0x03 {
+        flag temperature_compensation, set_temperature_compensation: 7;
+        flag reboot, set_reboot: 6;
+        flag soft_reset, set_soft_reset: 5;
+        flag low_power, set_low_power: 4;
+        field data_rate, set_data_rate: u8 = 0b0000_1100;
+        field mode, set_mode: u8 = 0b0000_0011;
+    }
+}
+
+register! {
+    /// LSM303AGR magnetometer.
+    CfgRegCM = 0x62, reset 0x00 {
+        flag interrupt_on_pin, set_interrupt_on_pin: 6;
+        flag i2c_disable, set_i2c_disable: 5;
+        flag block_data_update, set_block_data_update: 4;
+        flag big_endian, set_big_endian: 3;
+        flag self_test, set_self_test: 1;
+        flag data_ready_on_pin, set_data_ready_on_pin: 0;
+    }
+}
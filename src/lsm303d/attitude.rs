@@ -0,0 +1,170 @@
+use libm::{acos, asin, atan2, cos, sin, sqrt};
+
+use super::Measurements;
+use super::compass::{normalize_degrees, Compass};
+
+/// Unit quaternion describing a rotation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// Euler angles in degrees, using the same conventions as `CompassReading`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EulerAngles {
+    /// Rotation around the x axis, -180..180.
+    pub roll: f64,
+    /// Rotation around the y axis, -90..90.
+    pub pitch: f64,
+    /// Heading, 0..360, clockwise from north.
+    pub yaw: f64,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+    }
+
+    /// Builds the rotation yaw, then pitch, then roll. Angles are in degrees.
+    pub fn from_euler(angles: &EulerAngles) -> Self {
+        let (sr, cr) = half_angle(angles.roll);
+        let (sp, cp) = half_angle(angles.pitch);
+        let (sy, cy) = half_angle(angles.yaw);
+
+        Self {
+            w: cr * cp * cy + sr * sp * sy,
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+        }
+    }
+
+    pub fn to_euler(&self) -> EulerAngles {
+        let roll = atan2(2.0 * (self.w * self.x + self.y * self.z), 1.0 - 2.0 * (self.x * self.x + self.y * self.y));
+        let pitch = asin((2.0 * (self.w * self.y - self.z * self.x)).clamp(-1.0, 1.0));
+        let yaw = atan2(2.0 * (self.w * self.z + self.x * self.y), 1.0 - 2.0 * (self.y * self.y + self.z * self.z));
+
+        EulerAngles {
+            roll: roll.to_degrees(),
+            pitch: pitch.to_degrees(),
+            yaw: normalize_degrees(yaw.to_degrees()),
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalized(&self) -> Self {
+        let length = sqrt(self.dot(self));
+        Self {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    /// Spherical interpolation along the shortest path, `t` = 0 gives `self`, 1 gives `other`.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Self {
+        let mut dot = self.dot(other);
+        // q and -q are the same rotation, take the one closer to self
+        let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+        dot *= sign;
+
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = acos(dot);
+            let sin_theta = sin(theta);
+            (sin((1.0 - t) * theta) / sin_theta, sin(t * theta) / sin_theta)
+        };
+
+        Self {
+            w: a * self.w + b * sign * other.w,
+            x: a * self.x + b * sign * other.x,
+            y: a * self.y + b * sign * other.y,
+            z: a * self.z + b * sign * other.z,
+        }.normalized()
+    }
+}
+
+/// Complementary filter smoothing the attitude derived from accelerometer and
+/// magnetometer samples.
+///
+/// Every update first predicts the attitude from the previous one and then corrects it
+/// towards the absolute attitude given by the `Compass`. Without a gyroscope the
+/// prediction keeps the previous attitude, which turns the filter into a low pass with
+/// the given time constant. Integrating angular rates in the prediction step turns it
+/// into a regular complementary filter without changing the correction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AttitudeEstimator {
+    pub compass: Compass,
+    /// Time constant in seconds. Longer values smooth more but react slower.
+    pub time_constant: f64,
+    /// Time between updates in seconds, usually the inverse of the data rate.
+    pub sample_period: f64,
+    attitude: Option<Quaternion>,
+}
+
+impl AttitudeEstimator {
+    pub fn new(compass: Compass, time_constant: f64, sample_period: f64) -> Self {
+        Self {
+            compass,
+            time_constant,
+            sample_period,
+            attitude: None,
+        }
+    }
+
+    pub fn attitude(&self) -> Option<Quaternion> {
+        self.attitude
+    }
+
+    pub fn euler(&self) -> Option<EulerAngles> {
+        self.attitude.map(|attitude| attitude.to_euler())
+    }
+
+    /// Forgets the current estimate, the next sample is taken as is.
+    pub fn reset(&mut self) {
+        self.attitude = None;
+    }
+
+    /// Feeds a new sample. Samples without an absolute orientation, e.g. in free fall,
+    /// only run the prediction. Returns `None` until the first usable sample.
+    pub fn update(&mut self, measurements: &Measurements) -> Option<Quaternion> {
+        let predicted = self.predict();
+        let measured = self.compass.read(measurements).map(|reading| {
+            Quaternion::from_euler(&EulerAngles {
+                roll: reading.roll,
+                pitch: reading.pitch,
+                yaw: reading.heading,
+            })
+        });
+
+        self.attitude = match (predicted, measured) {
+            (Some(predicted), Some(measured)) => Some(predicted.slerp(&measured, self.gain())),
+            (predicted, measured) => predicted.or(measured),
+        };
+
+        self.attitude
+    }
+
+    fn predict(&self) -> Option<Quaternion> {
+        self.attitude
+    }
+
+    /// Weight of the measured attitude in each update.
+    fn gain(&self) -> f64 {
+        let gain = self.sample_period / (self.time_constant + self.sample_period);
+        if gain.is_finite() { gain.clamp(0.0, 1.0) } else { 1.0 }
+    }
+}
+
+fn half_angle(degrees: f64) -> (f64, f64) {
+    let radians = degrees.to_radians() / 2.0;
+    (sin(radians), cos(radians))
+}
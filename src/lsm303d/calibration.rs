@@ -0,0 +1,653 @@
+use embedded_hal::blocking::delay::DelayMs;
+use libm::{fabs, pow, sqrt};
+
+use super::{LSM303D, AccelerometerMeasurements, MagnetometerMeasurements, Measurements, Register};
+use super::transport::Transport;
+
+pub const MAGNETOMETER_CALIBRATION_SIZE: usize = 96;
+
+/// Hard-iron offset and soft-iron matrix, both in gauss.
+/// A corrected vector is `soft_iron * (raw - offset)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MagnetometerCalibration {
+    pub offset: MagnetometerMeasurements,
+    pub soft_iron: [[f64; 3]; 3],
+}
+
+impl Default for MagnetometerCalibration {
+    fn default() -> Self {
+        Self {
+            offset: MagnetometerMeasurements::default(),
+            soft_iron: [
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ],
+        }
+    }
+}
+
+impl MagnetometerCalibration {
+    pub fn apply(&self, measurements: &MagnetometerMeasurements) -> MagnetometerMeasurements {
+        self.apply_soft_iron(&MagnetometerMeasurements {
+            x: measurements.x - self.offset.x,
+            y: measurements.y - self.offset.y,
+            z: measurements.z - self.offset.z,
+        })
+    }
+
+    /// Applies only the soft-iron matrix, for readings where the chip already subtracted
+    /// the hard-iron offset (see `LSM303D::write_magnetometer_offset`).
+    pub fn apply_soft_iron(&self, measurements: &MagnetometerMeasurements) -> MagnetometerMeasurements {
+        let v = [measurements.x, measurements.y, measurements.z];
+        let m = &self.soft_iron;
+
+        MagnetometerMeasurements {
+            x: m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            y: m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            z: m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+        }
+    }
+
+    /// Serializes the calibration as twelve little-endian f64 values:
+    /// offset x, y, z followed by the soft-iron matrix in row-major order.
+    pub fn to_bytes(&self) -> [u8; MAGNETOMETER_CALIBRATION_SIZE] {
+        let mut bytes = [0u8; MAGNETOMETER_CALIBRATION_SIZE];
+        let values = [self.offset.x, self.offset.y, self.offset.z]
+            .into_iter()
+            .chain(self.soft_iron.iter().flatten().copied());
+
+        for (chunk, value) in bytes.chunks_exact_mut(8).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; MAGNETOMETER_CALIBRATION_SIZE]) -> Result<Self, ()> {
+        let mut values = [0f64; 12];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(8)) {
+            *value = f64::from_le_bytes(chunk.try_into().unwrap());
+            if !value.is_finite() {
+                return Err(());
+            }
+        }
+
+        Ok(Self {
+            offset: MagnetometerMeasurements {
+                x: values[0],
+                y: values[1],
+                z: values[2],
+            },
+            soft_iron: [
+                [values[3], values[4], values[5]],
+                [values[6], values[7], values[8]],
+                [values[9], values[10], values[11]],
+            ],
+        })
+    }
+}
+
+/// Per-axis zero-g offset and scale in g. A corrected value is `(raw - offset) * scale`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AccelerometerCalibration {
+    pub offset: AccelerometerMeasurements,
+    pub scale: AccelerometerMeasurements,
+}
+
+impl Default for AccelerometerCalibration {
+    fn default() -> Self {
+        Self {
+            offset: AccelerometerMeasurements::default(),
+            scale: AccelerometerMeasurements {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        }
+    }
+}
+
+impl AccelerometerCalibration {
+    pub fn apply(&self, measurements: &AccelerometerMeasurements) -> AccelerometerMeasurements {
+        AccelerometerMeasurements {
+            x: (measurements.x - self.offset.x) * self.scale.x,
+            y: (measurements.y - self.offset.y) * self.scale.y,
+            z: (measurements.z - self.offset.z) * self.scale.z,
+        }
+    }
+}
+
+/// Face of the device that has to point down during a calibration step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Face {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::PositiveX,
+        Face::NegativeX,
+        Face::PositiveY,
+        Face::NegativeY,
+        Face::PositiveZ,
+        Face::NegativeZ,
+    ];
+}
+
+/// Accumulates magnetometer samples and fits an ellipsoid
+/// `Ax² + By² + Cz² + 2Dxy + 2Exz + 2Fyz + 2Gx + 2Hy + 2Iz = 1` to them.
+/// Only the normal equations are stored, so any number of samples can be fed.
+pub struct MagnetometerCalibrator {
+    normal: [[f64; 9]; 9],
+    rhs: [f64; 9],
+    samples: usize,
+}
+
+impl Default for MagnetometerCalibrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MagnetometerCalibrator {
+    pub fn new() -> Self {
+        Self {
+            normal: [[0.0; 9]; 9],
+            rhs: [0.0; 9],
+            samples: 0,
+        }
+    }
+
+    pub fn add_sample(&mut self, measurements: &MagnetometerMeasurements) {
+        let (x, y, z) = (measurements.x, measurements.y, measurements.z);
+        let row = [
+            x * x,
+            y * y,
+            z * z,
+            2.0 * x * y,
+            2.0 * x * z,
+            2.0 * y * z,
+            2.0 * x,
+            2.0 * y,
+            2.0 * z,
+        ];
+
+        for i in 0..9 {
+            for j in 0..9 {
+                self.normal[i][j] += row[i] * row[j];
+            }
+            self.rhs[i] += row[i];
+        }
+        self.samples += 1;
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Fits the ellipsoid. Fails if there are too few samples or they do not cover
+    /// enough orientations to describe an ellipsoid.
+    pub fn compute(&self) -> Result<MagnetometerCalibration, ()> {
+        if self.samples < 9 {
+            return Err(());
+        }
+
+        let p = solve(self.normal, self.rhs).ok_or(())?;
+        let quadric = [
+            [p[0], p[3], p[4]],
+            [p[3], p[1], p[5]],
+            [p[4], p[5], p[2]],
+        ];
+        let linear = [p[6], p[7], p[8]];
+
+        let inverse = solve3(quadric).ok_or(())?;
+        let center = [
+            -(inverse[0][0] * linear[0] + inverse[0][1] * linear[1] + inverse[0][2] * linear[2]),
+            -(inverse[1][0] * linear[0] + inverse[1][1] * linear[1] + inverse[1][2] * linear[2]),
+            -(inverse[2][0] * linear[0] + inverse[2][1] * linear[1] + inverse[2][2] * linear[2]),
+        ];
+
+        // (v - c)ᵀ Q (v - c) = 1 + cᵀ Q c
+        let mut k = 1.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                k += center[i] * quadric[i][j] * center[j];
+            }
+        }
+        if k <= 0.0 {
+            return Err(());
+        }
+
+        let mut normalized = quadric;
+        normalized.iter_mut().flatten().for_each(|v| *v /= k);
+
+        let (eigenvalues, eigenvectors) = eigen_symmetric(normalized);
+        if eigenvalues.iter().any(|v| *v <= 0.0) {
+            return Err(());
+        }
+
+        // Scale so that the corrected sphere keeps the mean radius of the ellipsoid.
+        let radius = pow(eigenvalues[0] * eigenvalues[1] * eigenvalues[2], -1.0 / 6.0);
+
+        let mut soft_iron = [[0.0; 3]; 3];
+        for (i, row) in soft_iron.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                for (k, eigenvalue) in eigenvalues.iter().enumerate() {
+                    *value += eigenvectors[i][k] * sqrt(*eigenvalue) * eigenvectors[j][k];
+                }
+                *value *= radius;
+            }
+        }
+
+        Ok(MagnetometerCalibration {
+            offset: MagnetometerMeasurements {
+                x: center[0],
+                y: center[1],
+                z: center[2],
+            },
+            soft_iron,
+        })
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Collects `samples` readings `interval_ms` apart while the device is being rotated
+    /// and fits a calibration. Hardware offsets are cleared first so the fit sees raw data.
+    pub fn calibrate_magnetometer<D: DelayMs<u16>>(
+        &mut self,
+        delay: &mut D,
+        samples: usize,
+        interval_ms: u16,
+    ) -> Result<MagnetometerCalibration, ()> {
+        self.write_magnetometer_offset(&MagnetometerMeasurements::default())?;
+
+        let mut calibrator = MagnetometerCalibrator::new();
+        for _ in 0..samples {
+            calibrator.add_sample(&self.read_measurements()?.magnetometer);
+            delay.delay_ms(interval_ms);
+        }
+
+        calibrator.compute()
+    }
+
+    /// Six-position accelerometer calibration. For every face `prompt` is called and should
+    /// return once the device rests with that face pointing down; `samples` readings taken
+    /// `interval_ms` apart are then averaged.
+    pub fn calibrate_accelerometer<D, F>(
+        &mut self,
+        delay: &mut D,
+        samples: usize,
+        interval_ms: u16,
+        mut prompt: F,
+    ) -> Result<AccelerometerCalibration, ()>
+    where
+        D: DelayMs<u16>,
+        F: FnMut(Face),
+    {
+        if samples == 0 {
+            return Err(());
+        }
+
+        // Readings with the axis pointing up (+1 g) and down (-1 g).
+        let mut up = [0.0; 3];
+        let mut down = [0.0; 3];
+
+        for face in Face::ALL {
+            prompt(face);
+
+            let mut sum = [0.0; 3];
+            for _ in 0..samples {
+                let acc = self.read_measurements()?.accelerometer;
+                sum[0] += acc.x;
+                sum[1] += acc.y;
+                sum[2] += acc.z;
+                delay.delay_ms(interval_ms);
+            }
+
+            let (axis, target) = match face {
+                Face::PositiveX => (0, &mut down),
+                Face::NegativeX => (0, &mut up),
+                Face::PositiveY => (1, &mut down),
+                Face::NegativeY => (1, &mut up),
+                Face::PositiveZ => (2, &mut down),
+                Face::NegativeZ => (2, &mut up),
+            };
+            target[axis] = sum[axis] / samples as f64;
+        }
+
+        if (0..3).any(|axis| up[axis] <= down[axis]) {
+            return Err(());
+        }
+
+        Ok(AccelerometerCalibration {
+            offset: AccelerometerMeasurements {
+                x: (up[0] + down[0]) / 2.0,
+                y: (up[1] + down[1]) / 2.0,
+                z: (up[2] + down[2]) / 2.0,
+            },
+            scale: AccelerometerMeasurements {
+                x: 2.0 / (up[0] - down[0]),
+                y: 2.0 / (up[1] - down[1]),
+                z: 2.0 / (up[2] - down[2]),
+            },
+        })
+    }
+
+    /// Writes a hard-iron offset in gauss into OFFSET_X/Y/Z_M, so the chip subtracts it
+    /// from every reading. Uses the full scale of the last `configure_magnetometer` call.
+    pub fn write_magnetometer_offset(&mut self, offset: &MagnetometerMeasurements) -> Result<(), ()> {
+        let to_raw = |value: f64| -> [u8; 2] {
+            let raw = value * 32678.0 / self.mag_divider;
+            (raw.clamp(i16::MIN.into(), i16::MAX.into()) as i16).to_le_bytes()
+        };
+        let x = to_raw(offset.x);
+        let y = to_raw(offset.y);
+        let z = to_raw(offset.z);
+
+        self.write_register(Register::OffsetXLM, x[0])?;
+        self.write_register(Register::OffsetXHM, x[1])?;
+        self.write_register(Register::OffsetYLM, y[0])?;
+        self.write_register(Register::OffsetYHM, y[1])?;
+        self.write_register(Register::OffsetZLM, z[0])?;
+        self.write_register(Register::OffsetZHM, z[1])?;
+
+        Ok(())
+    }
+}
+
+/// Wraps a driver and applies an accelerometer calibration to every reading.
+pub struct CalibratedLSM303D<T> {
+    lsm303d: LSM303D<T>,
+    calibration: AccelerometerCalibration,
+}
+
+impl<T> CalibratedLSM303D<T>
+where T: Transport {
+    pub fn new(lsm303d: LSM303D<T>, calibration: AccelerometerCalibration) -> Self {
+        Self {
+            lsm303d,
+            calibration,
+        }
+    }
+
+    pub fn calibration(&self) -> &AccelerometerCalibration {
+        &self.calibration
+    }
+
+    pub fn set_calibration(&mut self, calibration: AccelerometerCalibration) {
+        self.calibration = calibration;
+    }
+
+    pub fn inner(&mut self) -> &mut LSM303D<T> {
+        &mut self.lsm303d
+    }
+
+    pub fn release(self) -> LSM303D<T> {
+        self.lsm303d
+    }
+
+    pub fn read_measurements(&mut self) -> Result<Measurements, ()> {
+        let mut measurements = self.lsm303d.read_measurements()?;
+        measurements.accelerometer = self.calibration.apply(&measurements.accelerometer);
+
+        Ok(measurements)
+    }
+}
+
+fn solve(mut a: [[f64; 9]; 9], mut b: [f64; 9]) -> Option<[f64; 9]> {
+    for col in 0..9 {
+        let pivot = (col..9).max_by(|i, j| fabs(a[*i][col]).total_cmp(&fabs(a[*j][col])))?;
+        if fabs(a[pivot][col]) < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in col + 1..9 {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (value, pivot) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = [0.0; 9];
+    for row in (0..9).rev() {
+        let mut sum = b[row];
+        for k in row + 1..9 {
+            sum -= a[row][k] * x[k];
+        }
+        x[row] = sum / a[row][row];
+    }
+
+    Some(x)
+}
+
+fn solve3(m: [[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if fabs(det) < 1e-12 {
+        return None;
+    }
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            let (a, b) = ((j + 1) % 3, (j + 2) % 3);
+            let (c, d) = ((i + 1) % 3, (i + 2) % 3);
+            *value = (m[a][c] * m[b][d] - m[a][d] * m[b][c]) / det;
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Jacobi eigenvalue decomposition of a symmetric 3×3 matrix.
+/// Returns the eigenvalues and a matrix with the eigenvectors as columns.
+fn eigen_symmetric(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+
+    for _ in 0..50 {
+        let off = a[0][1] * a[0][1] + a[0][2] * a[0][2] + a[1][2] * a[1][2];
+        if off < 1e-24 {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if fabs(a[p][q]) < 1e-300 {
+                continue;
+            }
+
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (fabs(theta) + sqrt(theta * theta + 1.0));
+            let c = 1.0 / sqrt(t * t + 1.0);
+            let s = t * c;
+
+            for row in a.iter_mut() {
+                let akp = row[p];
+                let akq = row[q];
+                row[p] = c * akp - s * akq;
+                row[q] = s * akp + c * akq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            for (k, (apk, aqk)) in row_p.iter().zip(row_q.iter()).enumerate() {
+                a[p][k] = c * apk - s * aqk;
+                a[q][k] = s * apk + c * aqk;
+            }
+            for row in v.iter_mut() {
+                let vkp = row[p];
+                let vkq = row[q];
+                row[p] = c * vkp - s * vkq;
+                row[q] = s * vkp + c * vkq;
+            }
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::PI;
+
+    use libm::{cos, sin};
+
+    use super::*;
+
+    const OFFSET: [f64; 3] = [0.12, -0.3, 0.05];
+    // Symmetric, positive definite distortion with differently scaled and coupled axes
+    const DISTORTION: [[f64; 3]; 3] = [
+        [1.2, 0.1, 0.05],
+        [0.1, 0.8, -0.08],
+        [0.05, -0.08, 1.0],
+    ];
+
+    fn multiply(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+        [
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+        ]
+    }
+
+    fn norm(v: &MagnetometerMeasurements) -> f64 {
+        sqrt(v.x * v.x + v.y * v.y + v.z * v.z)
+    }
+
+    /// Points of a 0.5 gauss field seen from orientations spread over the sphere.
+    fn sphere() -> impl Iterator<Item = [f64; 3]> {
+        (1..12).flat_map(|i| {
+            let polar = PI * f64::from(i) / 12.0;
+            (0..24).map(move |j| {
+                let azimuth = 2.0 * PI * f64::from(j) / 24.0;
+                [0.5 * sin(polar) * cos(azimuth), 0.5 * sin(polar) * sin(azimuth), 0.5 * cos(polar)]
+            })
+        })
+    }
+
+    fn distorted(v: [f64; 3]) -> MagnetometerMeasurements {
+        let v = multiply(&DISTORTION, v);
+        MagnetometerMeasurements { x: v[0] + OFFSET[0], y: v[1] + OFFSET[1], z: v[2] + OFFSET[2] }
+    }
+
+    #[test]
+    fn fit_recovers_offset_and_soft_iron() {
+        let mut calibrator = MagnetometerCalibrator::new();
+        sphere().for_each(|v| calibrator.add_sample(&distorted(v)));
+        let calibration = calibrator.compute().unwrap();
+
+        let offset = [calibration.offset.x, calibration.offset.y, calibration.offset.z];
+        for (fitted, expected) in offset.iter().zip(OFFSET) {
+            assert!(fabs(fitted - expected) < 1e-9, "offset {offset:?}");
+        }
+
+        // The corrected samples lie on a sphere with the mean radius of the ellipsoid
+        let m = DISTORTION;
+        let determinant = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        let radius = 0.5 * pow(determinant, 1.0 / 3.0);
+        for v in sphere() {
+            let corrected = calibration.apply(&distorted(v));
+            assert!(fabs(norm(&corrected) - radius) < 1e-9, "radius {}", norm(&corrected));
+        }
+    }
+
+    #[test]
+    fn fit_rejects_too_few_samples() {
+        let mut calibrator = MagnetometerCalibrator::new();
+        sphere().take(8).for_each(|v| calibrator.add_sample(&distorted(v)));
+
+        assert_eq!(calibrator.samples(), 8);
+        assert!(calibrator.compute().is_err());
+    }
+
+    #[test]
+    fn fit_rejects_planar_samples() {
+        // Rotating the device only around its z axis never shows the z scale
+        let mut calibrator = MagnetometerCalibrator::new();
+        for j in 0..36 {
+            let azimuth = 2.0 * PI * f64::from(j) / 36.0;
+            calibrator.add_sample(&MagnetometerMeasurements { x: 0.4 * cos(azimuth), y: 0.3 * sin(azimuth), z: 0.1 });
+        }
+
+        assert!(calibrator.compute().is_err());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let calibration = MagnetometerCalibration {
+            offset: MagnetometerMeasurements { x: 0.12, y: -0.3, z: 0.05 },
+            soft_iron: DISTORTION,
+        };
+
+        let bytes = calibration.to_bytes();
+        assert_eq!(&bytes[..8], &0.12f64.to_le_bytes());
+        assert_eq!(MagnetometerCalibration::from_bytes(&bytes), Ok(calibration));
+    }
+
+    #[test]
+    fn from_bytes_rejects_non_finite_values() {
+        let mut bytes = MagnetometerCalibration::default().to_bytes();
+        bytes[40..48].copy_from_slice(&f64::NAN.to_le_bytes());
+
+        assert!(MagnetometerCalibration::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn solve_finds_exact_solution() {
+        let mut a = [[0.0; 9]; 9];
+        for (i, row) in a.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = if i == j { 4.0 } else { 1.0 / (1.0 + (i + j) as f64) };
+            }
+        }
+        let expected = [1.0, -2.0, 3.0, 0.5, 0.0, -1.5, 2.5, 4.0, -3.0];
+        let mut b = [0.0; 9];
+        for (i, value) in b.iter_mut().enumerate() {
+            *value = (0..9).map(|j| a[i][j] * expected[j]).sum();
+        }
+
+        let x = solve(a, b).unwrap();
+        for (x, expected) in x.iter().zip(expected) {
+            assert!(fabs(x - expected) < 1e-12);
+        }
+        assert!(solve([[0.0; 9]; 9], b).is_none());
+    }
+
+    #[test]
+    fn eigen_decomposition_reconstructs_matrix() {
+        let (eigenvalues, eigenvectors) = eigen_symmetric(DISTORTION);
+
+        for (k, eigenvalue) in eigenvalues.iter().enumerate() {
+            let v = [eigenvectors[0][k], eigenvectors[1][k], eigenvectors[2][k]];
+            let av = multiply(&DISTORTION, v);
+            for i in 0..3 {
+                assert!(fabs(av[i] - eigenvalue * v[i]) < 1e-12);
+            }
+        }
+
+        let inverse = solve3(DISTORTION).unwrap();
+        for (i, row) in DISTORTION.iter().enumerate() {
+            let product = multiply(&inverse, *row);
+            for (j, value) in product.iter().enumerate() {
+                // Rows of a symmetric matrix are its columns, so this is the identity
+                assert!(fabs(value - if i == j { 1.0 } else { 0.0 }) < 1e-12);
+            }
+        }
+    }
+}
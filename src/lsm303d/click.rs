@@ -0,0 +1,119 @@
+use super::LSM303D;
+use super::registers::{ClickCfg, ClickSrc, ClickThs, TimeLatency, TimeLimit, TimeWindow};
+use super::transport::Transport;
+use crate::register::{Register as _, RegisterAccess};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClickMode {
+    Single,
+    Double,
+    SingleAndDouble,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClickConfiguration {
+    pub axis_x: bool,
+    pub axis_y: bool,
+    pub axis_z: bool,
+
+    pub mode: ClickMode,
+    /// Acceleration threshold in g, limited by the configured full scale.
+    pub threshold: f64,
+    /// Maximum time the acceleration may stay above threshold to count as a click.
+    pub time_limit_ms: f64,
+    /// Dead time after the first click before the second one is looked for.
+    pub time_latency_ms: f64,
+    /// Time after the latency in which the second click has to start.
+    pub time_window_ms: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClickDirection {
+    Positive,
+    Negative,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClickSource {
+    pub active: bool,
+    pub single: bool,
+    pub double: bool,
+    pub direction: ClickDirection,
+    pub axis_x: bool,
+    pub axis_y: bool,
+    pub axis_z: bool,
+}
+
+impl From<ClickSrc> for ClickSource {
+    fn from(source: ClickSrc) -> Self {
+        Self {
+            active: source.active(),
+            double: source.double(),
+            single: source.single(),
+            direction: if source.negative() { ClickDirection::Negative } else { ClickDirection::Positive },
+            axis_z: source.z(),
+            axis_y: source.y(),
+            axis_x: source.x(),
+        }
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Configures click detection. Timings are converted using the data rate of the last
+    /// `configure_accelerometer` call, so the accelerometer has to be configured first.
+    pub fn configure_click(&mut self, configuration: ClickConfiguration) -> Result<(), ()> {
+        let frequency = self.acc_data_rate.frequency();
+        if frequency == 0.0 {
+            return Err(());
+        }
+
+        let (single, double) = match configuration.mode {
+            ClickMode::Single => (true, false),
+            ClickMode::Double => (false, true),
+            ClickMode::SingleAndDouble => (true, true),
+        };
+
+        let mut cfg = ClickCfg::reset();
+        cfg
+            .set_x_single(configuration.axis_x && single)
+            .set_x_double(configuration.axis_x && double)
+            .set_y_single(configuration.axis_y && single)
+            .set_y_double(configuration.axis_y && double)
+            .set_z_single(configuration.axis_z && single)
+            .set_z_double(configuration.axis_z && double);
+
+        let mut threshold = ClickThs::reset();
+        threshold.set_threshold(g_to_threshold(configuration.threshold, self.acc_divider));
+
+        let mut limit = TimeLimit::reset();
+        limit.set_limit(ms_to_samples(configuration.time_limit_ms, frequency).min(0x7F));
+        let mut latency = TimeLatency::reset();
+        latency.set_latency(ms_to_samples(configuration.time_latency_ms, frequency));
+        let mut window = TimeWindow::reset();
+        window.set_window(ms_to_samples(configuration.time_window_ms, frequency));
+
+        self.transport.write(threshold)?;
+        self.transport.write(limit)?;
+        self.transport.write(latency)?;
+        self.transport.write(window)?;
+        self.transport.write(cfg)?;
+
+        Ok(())
+    }
+
+    /// Reads CLICK_SRC, which also clears a latched click interrupt.
+    pub fn read_click_source(&mut self) -> Result<ClickSource, ()> {
+        Ok(self.transport.read::<ClickSrc>()?.into())
+    }
+}
+
+pub(super) fn ms_to_samples(ms: f64, frequency: f64) -> u8 {
+    (ms * frequency / 1000.0 + 0.5) as u8
+}
+
+/// Converts an acceleration in g into the 7 bit threshold of the click and interrupt
+/// generators, where one LSB equals 1/128 of the full scale.
+pub(super) fn g_to_threshold(g: f64, full_scale: f64) -> u8 {
+    ((g * 128.0 / full_scale + 0.5) as u8).min(0x7F)
+}
@@ -0,0 +1,84 @@
+use libm::{atan2, cos, sin, sqrt};
+
+use super::{Measurements, MagnetometerMeasurements};
+use super::calibration::MagnetometerCalibration;
+
+// Weight of the x axis in the roll denominator, keeps roll defined when the device is vertical.
+const ROLL_STABILIZATION: f64 = 0.01;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CompassReading {
+    /// Rotation around the x axis in degrees, -180..180.
+    pub roll: f64,
+    /// Rotation around the y axis in degrees, -90..90.
+    pub pitch: f64,
+    /// Tilt compensated heading in degrees, 0..360, clockwise from north.
+    pub heading: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Compass {
+    pub calibration: Option<MagnetometerCalibration>,
+    /// Magnetic declination in degrees, east positive. Zero gives magnetic heading.
+    pub declination: f64,
+}
+
+impl Compass {
+    pub fn new(calibration: Option<MagnetometerCalibration>, declination: f64) -> Self {
+        Self {
+            calibration,
+            declination,
+        }
+    }
+
+    /// Computes roll, pitch and heading. Returns `None` when either vector is zero,
+    /// e.g. in free fall, because no orientation can be derived then.
+    pub fn read(&self, measurements: &Measurements) -> Option<CompassReading> {
+        let acc = &measurements.accelerometer;
+        let mag = match &self.calibration {
+            Some(calibration) => calibration.apply(&measurements.magnetometer),
+            None => measurements.magnetometer,
+        };
+
+        if is_zero(acc.x, acc.y, acc.z) || is_zero(mag.x, mag.y, mag.z) {
+            return None;
+        }
+
+        let roll_sign = if acc.z < 0.0 { -1.0 } else { 1.0 };
+        let roll = atan2(acc.y, roll_sign * sqrt(acc.z * acc.z + ROLL_STABILIZATION * acc.x * acc.x));
+        let (sin_roll, cos_roll) = (sin(roll), cos(roll));
+
+        let pitch = atan2(-acc.x, acc.y * sin_roll + acc.z * cos_roll);
+        let (sin_pitch, cos_pitch) = (sin(pitch), cos(pitch));
+
+        let heading = tilt_compensated_heading(&mag, sin_roll, cos_roll, sin_pitch, cos_pitch);
+
+        Some(CompassReading {
+            roll: roll.to_degrees(),
+            pitch: pitch.to_degrees(),
+            heading: normalize_degrees(heading.to_degrees() + self.declination),
+        })
+    }
+}
+
+fn tilt_compensated_heading(
+    mag: &MagnetometerMeasurements,
+    sin_roll: f64,
+    cos_roll: f64,
+    sin_pitch: f64,
+    cos_pitch: f64,
+) -> f64 {
+    let by = mag.z * sin_roll - mag.y * cos_roll;
+    let bx = mag.x * cos_pitch + mag.y * sin_pitch * sin_roll + mag.z * sin_pitch * cos_roll;
+
+    atan2(by, bx)
+}
+
+fn is_zero(x: f64, y: f64, z: f64) -> bool {
+    x * x + y * y + z * z < f64::EPSILON
+}
+
+pub(super) fn normalize_degrees(value: f64) -> f64 {
+    let value = value % 360.0;
+    if value < 0.0 { value + 360.0 } else { value }
+}
@@ -0,0 +1,148 @@
+use core::fmt;
+
+use super::{
+    LSM303D,
+    AccelerometerConfiguration,
+    MagnetometerConfiguration,
+    decode_accelerometer_configuration,
+    decode_magnetometer_configuration,
+};
+use super::registers::{Ctrl0, Ctrl1, Ctrl2, Ctrl5, Ctrl6, Ctrl7, StatusA, StatusM};
+use super::status::Status;
+use crate::register::Register;
+use super::transport::Transport;
+
+// Source registers (INT_SRC_M, IG_SRC1/2, CLICK_SRC) are left out because reading
+// them clears latched interrupts.
+static REGISTERS: [(&str, u8); 46] = [
+    ("TEMP_OUT_L", 0x05),
+    ("TEMP_OUT_H", 0x06),
+    ("STATUS_M", 0x07),
+    ("OUT_X_L_M", 0x08),
+    ("OUT_X_H_M", 0x09),
+    ("OUT_Y_L_M", 0x0A),
+    ("OUT_Y_H_M", 0x0B),
+    ("OUT_Z_L_M", 0x0C),
+    ("OUT_Z_H_M", 0x0D),
+    ("WHO_AM_I", 0x0F),
+    ("INT_CTRL_M", 0x12),
+    ("INT_THS_L_M", 0x14),
+    ("INT_THS_H_M", 0x15),
+    ("OFFSET_X_L_M", 0x16),
+    ("OFFSET_X_H_M", 0x17),
+    ("OFFSET_Y_L_M", 0x18),
+    ("OFFSET_Y_H_M", 0x19),
+    ("OFFSET_Z_L_M", 0x1A),
+    ("OFFSET_Z_H_M", 0x1B),
+    ("REFERENCE_X", 0x1C),
+    ("REFERENCE_Y", 0x1D),
+    ("REFERENCE_Z", 0x1E),
+    ("CTRL0", 0x1F),
+    ("CTRL1", 0x20),
+    ("CTRL2", 0x21),
+    ("CTRL3", 0x22),
+    ("CTRL4", 0x23),
+    ("CTRL5", 0x24),
+    ("CTRL6", 0x25),
+    ("CTRL7", 0x26),
+    ("STATUS_A", 0x27),
+    ("FIFO_CTRL", 0x2E),
+    ("FIFO_SRC", 0x2F),
+    ("IG_CFG1", 0x30),
+    ("IG_THS1", 0x32),
+    ("IG_DUR1", 0x33),
+    ("IG_CFG2", 0x34),
+    ("IG_THS2", 0x36),
+    ("IG_DUR2", 0x37),
+    ("CLICK_CFG", 0x38),
+    ("CLICK_THS", 0x3A),
+    ("TIME_LIMIT", 0x3B),
+    ("TIME_LATENCY", 0x3C),
+    ("TIME_WINDOW", 0x3D),
+    ("ACT_THS", 0x3E),
+    ("ACT_DUR", 0x3F),
+];
+
+/// Snapshot of the readable registers, with the control registers decoded.
+pub struct RegisterDump {
+    values: [u8; REGISTERS.len()],
+    /// `None` if the registers hold a reserved data rate or scale.
+    pub accelerometer: Option<AccelerometerConfiguration>,
+    pub magnetometer: Option<MagnetometerConfiguration>,
+    /// CTRL5 holds the reserved data rate it has after power-on, so the magnetometer was
+    /// not configured yet and `magnetometer` is `None`.
+    pub magnetometer_unconfigured: bool,
+    pub temperature_enabled: bool,
+    pub accelerometer_status: Status,
+    pub magnetometer_status: Status,
+}
+
+impl RegisterDump {
+    /// Iterates over `(name, address, value)` of every dumped register.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u8, u8)> + '_ {
+        REGISTERS
+            .iter()
+            .zip(self.values.iter())
+            .map(|((name, address), value)| (*name, *address, *value))
+    }
+
+    pub fn get(&self, address: u8) -> Option<u8> {
+        self.iter().find(|(_, a, _)| *a == address).map(|(_, _, value)| value)
+    }
+}
+
+impl fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, address, value) in self.iter() {
+            writeln!(f, "{:<13} 0x{:02X} = 0x{:02X} (0b{:08b})", name, address, value, value)?;
+        }
+        writeln!(f, "accelerometer: {:?}", self.accelerometer)?;
+        if self.magnetometer_unconfigured {
+            writeln!(f, "magnetometer: not configured since power-on")?;
+        } else {
+            writeln!(f, "magnetometer: {:?}", self.magnetometer)?;
+        }
+        writeln!(f, "temperature enabled: {}", self.temperature_enabled)?;
+        writeln!(f, "accelerometer status: {:?}", self.accelerometer_status)?;
+        write!(f, "magnetometer status: {:?}", self.magnetometer_status)
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    pub fn dump_registers(&mut self) -> Result<RegisterDump, ()> {
+        let mut values = [0u8; REGISTERS.len()];
+        for (value, (_, address)) in values.iter_mut().zip(REGISTERS.iter()) {
+            let mut buffer = [0u8; 1];
+            self.transport.read_registers(*address, &mut buffer)?;
+            *value = buffer[0];
+        }
+
+        let register = |address: u8| {
+            REGISTERS
+                .iter()
+                .position(|(_, a)| *a == address)
+                .map(|index| values[index])
+                .unwrap_or(0)
+        };
+
+        Ok(RegisterDump {
+            accelerometer: decode_accelerometer_configuration(
+                Ctrl0::from_bits(register(Ctrl0::ADDRESS)),
+                Ctrl1::from_bits(register(Ctrl1::ADDRESS)),
+                Ctrl2::from_bits(register(Ctrl2::ADDRESS)),
+                Ctrl7::from_bits(register(Ctrl7::ADDRESS)),
+            ),
+            magnetometer: decode_magnetometer_configuration(
+                Ctrl5::from_bits(register(Ctrl5::ADDRESS)),
+                Ctrl6::from_bits(register(Ctrl6::ADDRESS)),
+                Ctrl7::from_bits(register(Ctrl7::ADDRESS)),
+            ),
+            magnetometer_unconfigured: Ctrl5::from_bits(register(Ctrl5::ADDRESS)).data_rate().is_none(),
+            temperature_enabled: Ctrl5::from_bits(register(Ctrl5::ADDRESS)).temperature_enable(),
+            accelerometer_status: StatusA::from_bits(register(StatusA::ADDRESS)).into(),
+            magnetometer_status: StatusM::from_bits(register(StatusM::ADDRESS)).into(),
+            values,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use libm::sqrt;
+
+use super::{AccelerometerMeasurements, AccelerationDataRate};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FreeFallConfiguration {
+    /// Magnitude in g below which the device counts as falling, around 0.3 g.
+    pub free_fall_threshold: f64,
+    /// Shortest fall that is reported. 100 ms correspond to a drop of about 5 cm.
+    pub min_duration_ms: f64,
+    /// Magnitude in g that counts as an impact.
+    pub impact_threshold: f64,
+    /// Time after the fall in which the peak acceleration is searched.
+    pub impact_window_ms: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DropEvent {
+    /// Start of the fall in ms, counted in samples since the detector was created.
+    pub timestamp_ms: u64,
+    /// Duration of the fall in ms.
+    pub duration_ms: f64,
+    /// Largest magnitude in g within the impact window.
+    pub peak: f64,
+    /// Whether the peak reached the impact threshold.
+    pub impact: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    Idle,
+    Falling { start: u64 },
+    Impact { start: u64, end: u64, peak: f64 },
+}
+
+/// Software free-fall detector keeping the last `N` drops.
+///
+/// A drop starts when the acceleration magnitude falls below the free fall threshold and
+/// is reported once the impact window after the fall has passed. Falls shorter than the
+/// minimum duration are ignored. When the log is full the oldest event is overwritten.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FreeFallDetector<const N: usize> {
+    configuration: FreeFallConfiguration,
+    sample_period_ms: f64,
+    samples: u64,
+    state: State,
+
+    events: [Option<DropEvent>; N],
+    next: usize,
+    overwritten: u32,
+}
+
+impl<const N: usize> FreeFallDetector<N> {
+    /// Returns `Err` for `AccelerationDataRate::PowerOff`.
+    pub fn new(configuration: FreeFallConfiguration, data_rate: AccelerationDataRate) -> Result<Self, ()> {
+        let frequency = data_rate.frequency();
+        if frequency == 0.0 {
+            return Err(());
+        }
+
+        Ok(Self {
+            configuration,
+            sample_period_ms: 1000.0 / frequency,
+            samples: 0,
+            state: State::Idle,
+
+            events: [None; N],
+            next: 0,
+            overwritten: 0,
+        })
+    }
+
+    /// Feeds the next sample. Returns the drop it completes, which is also logged.
+    pub fn update(&mut self, measurements: &AccelerometerMeasurements) -> Option<DropEvent> {
+        let magnitude = sqrt(
+            measurements.x * measurements.x + measurements.y * measurements.y + measurements.z * measurements.z
+        );
+        let now = self.samples;
+        self.samples += 1;
+
+        let falling = magnitude < self.configuration.free_fall_threshold;
+
+        match self.state {
+            State::Idle => {
+                if falling {
+                    self.state = State::Falling { start: now };
+                }
+                None
+            }
+            State::Falling { start } => {
+                if !falling {
+                    self.state = if self.duration_ms(start, now) >= self.configuration.min_duration_ms {
+                        State::Impact { start, end: now, peak: magnitude }
+                    } else {
+                        State::Idle
+                    };
+                }
+                None
+            }
+            State::Impact { start, end, peak } => {
+                let peak = peak.max(magnitude);
+                if self.duration_ms(end, now) < self.configuration.impact_window_ms {
+                    self.state = State::Impact { start, end, peak };
+                    return None;
+                }
+
+                // Bounces can start a new fall right after the window
+                self.state = if falling { State::Falling { start: now } } else { State::Idle };
+
+                let event = DropEvent {
+                    timestamp_ms: (start as f64 * self.sample_period_ms) as u64,
+                    duration_ms: self.duration_ms(start, end),
+                    peak,
+                    impact: peak >= self.configuration.impact_threshold,
+                };
+                self.log(event);
+
+                Some(event)
+            }
+        }
+    }
+
+    /// Logged events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &DropEvent> {
+        let (newer, older) = self.events.split_at(self.next);
+        older.iter().chain(newer.iter()).filter_map(|event| event.as_ref())
+    }
+
+    /// Number of events lost because the log was full.
+    pub fn overwritten(&self) -> u32 {
+        self.overwritten
+    }
+
+    /// Empties the log. A drop in progress is still reported.
+    pub fn clear(&mut self) {
+        self.events = [None; N];
+        self.next = 0;
+        self.overwritten = 0;
+    }
+
+    fn log(&mut self, event: DropEvent) {
+        if N == 0 {
+            return;
+        }
+        if self.events[self.next].is_some() {
+            self.overwritten = self.overwritten.saturating_add(1);
+        }
+        self.events[self.next] = Some(event);
+        self.next = (self.next + 1) % N;
+    }
+
+    fn duration_ms(&self, from: u64, to: u64) -> f64 {
+        (to - from) as f64 * self.sample_period_ms
+    }
+}
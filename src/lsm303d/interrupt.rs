@@ -0,0 +1,93 @@
+use super::{LSM303D, Register};
+use super::registers::{Ctrl3, Ctrl4, IntCtrlM, IntSrcM};
+use super::transport::Transport;
+use crate::register::RegisterAccess;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterruptPolarity {
+    ActiveLow,
+    ActiveHigh,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MagnetometerInterruptConfiguration {
+    pub axis_x: bool,
+    pub axis_y: bool,
+    pub axis_z: bool,
+    /// Absolute threshold in gauss, applied to both positive and negative values.
+    pub threshold: f64,
+    pub polarity: InterruptPolarity,
+    pub open_drain: bool,
+    /// Keep the interrupt active until INT_SRC_M is read.
+    pub latch: bool,
+    pub int1: bool,
+    pub int2: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MagnetometerInterruptSource {
+    pub positive_x: bool,
+    pub positive_y: bool,
+    pub positive_z: bool,
+    pub negative_x: bool,
+    pub negative_y: bool,
+    pub negative_z: bool,
+    /// Internal measurement range overflow.
+    pub overflow: bool,
+    pub active: bool,
+}
+
+impl From<IntSrcM> for MagnetometerInterruptSource {
+    fn from(source: IntSrcM) -> Self {
+        Self {
+            positive_x: source.positive_x(),
+            positive_y: source.positive_y(),
+            positive_z: source.positive_z(),
+            negative_x: source.negative_x(),
+            negative_y: source.negative_y(),
+            negative_z: source.negative_z(),
+            overflow: source.overflow(),
+            active: source.active(),
+        }
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Configures the magnetometer threshold interrupt. The threshold is converted using
+    /// the full scale of the last `configure_magnetometer` call.
+    pub fn configure_magnetometer_interrupt(&mut self, configuration: MagnetometerInterruptConfiguration) -> Result<(), ()> {
+        let threshold = (configuration.threshold * 32678.0 / self.mag_divider).clamp(0.0, 32767.0) as u16;
+        let threshold = threshold.to_le_bytes();
+        self.write_register(Register::IntThsLM, threshold[0])?;
+        self.write_register(Register::IntThsHM, threshold[1])?;
+
+        let enabled = configuration.axis_x || configuration.axis_y || configuration.axis_z;
+
+        // 4D detection shares the register and is left untouched
+        self.transport.modify(|int_ctrl: &mut IntCtrlM| {
+            int_ctrl
+                .set_axis_x(configuration.axis_x)
+                .set_axis_y(configuration.axis_y)
+                .set_axis_z(configuration.axis_z)
+                .set_open_drain(configuration.open_drain)
+                .set_active_high(configuration.polarity == InterruptPolarity::ActiveHigh)
+                .set_not_latched(!configuration.latch)
+                .set_enable(enabled);
+        })?;
+
+        self.transport.modify(|ctrl3: &mut Ctrl3| {
+            ctrl3.set_int1_magnetometer(configuration.int1);
+        })?;
+        self.transport.modify(|ctrl4: &mut Ctrl4| {
+            ctrl4.set_int2_magnetometer(configuration.int2);
+        })?;
+
+        Ok(())
+    }
+
+    /// Reads INT_SRC_M, which also clears a latched interrupt.
+    pub fn read_magnetometer_interrupt_source(&mut self) -> Result<MagnetometerInterruptSource, ()> {
+        Ok(self.transport.read::<IntSrcM>()?.into())
+    }
+}
@@ -0,0 +1,139 @@
+use embedded_hal::blocking::delay::DelayMs;
+
+use super::{LSM303D, AccelerationDataRate, AccelerationFullScale, MagneticSensorMode, MagnetometerFullScale};
+use super::registers::{
+    ClickCfg,
+    ClickThs,
+    Ctrl0,
+    Ctrl1,
+    Ctrl2,
+    Ctrl3,
+    Ctrl4,
+    Ctrl5,
+    Ctrl6,
+    Ctrl7,
+    IntCtrlM,
+    TimeLatency,
+    TimeLimit,
+    TimeWindow,
+};
+use super::transport::Transport;
+use crate::register::{Register as _, RegisterAccess};
+
+// Registers without bitfields that reset to zero: magnetic interrupt threshold, offsets,
+// high-pass references, FIFO control, interrupt generators and sleep-to-wake.
+static ZERO_RESET: [u8; 20] = [
+    0x14, 0x15,
+    0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B,
+    0x1C, 0x1D, 0x1E,
+    0x2E,
+    0x30, 0x32, 0x33,
+    0x34, 0x36, 0x37,
+    0x3E, 0x3F,
+];
+
+const BOOT_TIMEOUT_MS: u16 = 50;
+
+/// Raw control registers as they were before `power_down`. Kept undecoded so any state
+/// the chip can be in, including reserved codes, is restored exactly.
+#[derive(Debug, Copy, Clone)]
+pub(super) struct SavedControl {
+    ctrl1: Ctrl1,
+    ctrl2: Ctrl2,
+    ctrl5: Ctrl5,
+    ctrl6: Ctrl6,
+    ctrl7: Ctrl7,
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Reloads the trimming parameters from internal memory and waits until the chip
+    /// clears the BOOT bit. User registers keep their values.
+    pub fn reboot<D: DelayMs<u16>>(&mut self, delay: &mut D) -> Result<(), ()> {
+        self.transport.modify(|ctrl0: &mut Ctrl0| {
+            ctrl0.set_boot(true);
+        })?;
+
+        for _ in 0..BOOT_TIMEOUT_MS {
+            delay.delay_ms(1);
+            let ctrl0: Ctrl0 = self.transport.read()?;
+            if !ctrl0.boot() {
+                return Ok(());
+            }
+        }
+
+        Err(())
+    }
+
+    /// Writes the datasheet reset value to every writable register, leaving both sensors
+    /// powered down, and forgets the registers saved for `resume`.
+    pub fn reset_to_defaults(&mut self) -> Result<(), ()> {
+        self.transport.write(Ctrl1::reset())?;
+        self.transport.write(Ctrl7::reset())?;
+        self.transport.write(Ctrl0::reset())?;
+        self.transport.write(Ctrl2::reset())?;
+        self.transport.write(Ctrl3::reset())?;
+        self.transport.write(Ctrl4::reset())?;
+        self.transport.write(Ctrl5::reset())?;
+        self.transport.write(Ctrl6::reset())?;
+        self.transport.write(IntCtrlM::reset())?;
+        self.transport.write(ClickCfg::reset())?;
+        self.transport.write(ClickThs::reset())?;
+        self.transport.write(TimeLimit::reset())?;
+        self.transport.write(TimeLatency::reset())?;
+        self.transport.write(TimeWindow::reset())?;
+        for address in ZERO_RESET {
+            self.transport.write_raw(address, 0x00)?;
+        }
+
+        self.acc_divider = AccelerationFullScale::Acc2G.range();
+        self.mag_divider = MagnetometerFullScale::Mag4Gauss.range();
+        self.acc_data_rate = AccelerationDataRate::PowerOff;
+        self.suspended = None;
+
+        Ok(())
+    }
+
+    /// Puts accelerometer and magnetometer into power-down. The control registers are
+    /// saved as they are, so this works whatever the chip was configured with. Calling
+    /// it again before `resume` keeps the first saved state.
+    pub fn power_down(&mut self) -> Result<(), ()> {
+        if self.suspended.is_none() {
+            self.suspended = Some(SavedControl {
+                ctrl1: self.transport.read()?,
+                ctrl2: self.transport.read()?,
+                ctrl5: self.transport.read()?,
+                ctrl6: self.transport.read()?,
+                ctrl7: self.transport.read()?,
+            });
+        }
+
+        self.transport.modify(|ctrl1: &mut Ctrl1| {
+            ctrl1.set_data_rate(AccelerationDataRate::PowerOff);
+        })?;
+        self.transport.modify(|ctrl7: &mut Ctrl7| {
+            ctrl7.set_magnetometer_mode(MagneticSensorMode::PowerDown);
+        })?;
+
+        Ok(())
+    }
+
+    /// Writes back the control registers saved by `power_down`. Does nothing if the
+    /// sensors were not powered down through this driver.
+    pub fn resume(&mut self) -> Result<(), ()> {
+        let saved = match self.suspended {
+            Some(saved) => saved,
+            None => return Ok(()),
+        };
+
+        // Scale and resolution first, so the sensors start with the right settings
+        self.transport.write(saved.ctrl2)?;
+        self.transport.write(saved.ctrl5)?;
+        self.transport.write(saved.ctrl6)?;
+        self.transport.write(saved.ctrl1)?;
+        self.transport.write(saved.ctrl7)?;
+        self.suspended = None;
+
+        Ok(())
+    }
+}
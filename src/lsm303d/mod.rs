@@ -1,6 +1,28 @@
-use embedded_hal::blocking::i2c::{WriteRead, Write, Read};
-
-static ADDRESS: u8 = 0x1D;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{WriteRead, Write};
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+pub mod attitude;
+pub mod calibration;
+pub mod click;
+pub mod compass;
+pub mod dump;
+pub mod free_fall;
+pub mod interrupt;
+pub mod lifecycle;
+pub mod orientation;
+pub mod pedometer;
+pub mod registers;
+pub mod self_test;
+pub mod status;
+pub mod transport;
+pub mod vibration;
+
+use crate::register::{Register as _, RegisterAccess};
+use registers::{Ctrl0, Ctrl1, Ctrl2, Ctrl5, Ctrl6, Ctrl7};
+use status::OverrunCount;
+use transport::{I2cTransport, SlaveAddress, SpiTransport, Transport};
 
 pub enum Register {
     TempOutL = 0x05,
@@ -16,6 +38,22 @@ pub enum Register {
 
     WhoAmI   = 0x0F,
 
+    IntCtrlM = 0x12,
+    IntSrcM  = 0x13,
+    IntThsLM = 0x14,
+    IntThsHM = 0x15,
+
+    OffsetXLM = 0x16,
+    OffsetXHM = 0x17,
+    OffsetYLM = 0x18,
+    OffsetYHM = 0x19,
+    OffsetZLM = 0x1A,
+    OffsetZHM = 0x1B,
+
+    ReferenceX = 0x1C,
+    ReferenceY = 0x1D,
+    ReferenceZ = 0x1E,
+
     Ctrl0    = 0x1F,
     Ctrl1    = 0x20, //Acceleration register
     Ctrl2    = 0x21,
@@ -24,6 +62,7 @@ pub enum Register {
     Ctrl5    = 0x24, // Temperature registers
     Ctrl6    = 0x25, // Magnetometer resolution
     Ctrl7    = 0x26,
+    StatusA  = 0x27,
 
     OutXLA   = 0x28,
     OutXHA   = 0x29,
@@ -31,6 +70,22 @@ pub enum Register {
     OutYHA   = 0x2B,
     OutZLA   = 0x2C,
     OutZHA   = 0x2D,
+
+    IgCfg1   = 0x30,
+    IgSrc1   = 0x31,
+    IgThs1   = 0x32,
+    IgDur1   = 0x33,
+    IgCfg2   = 0x34,
+    IgSrc2   = 0x35,
+    IgThs2   = 0x36,
+    IgDur2   = 0x37,
+
+    ClickCfg    = 0x38,
+    ClickSrc    = 0x39,
+    ClickThs    = 0x3A,
+    TimeLimit   = 0x3B,
+    TimeLatency = 0x3C,
+    TimeWindow  = 0x3D,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -49,6 +104,24 @@ pub enum AccelerationDataRate {
     Hz1600   = 0b1010_0000,
 }
 
+impl AccelerationDataRate {
+    pub fn frequency(&self) -> f64 {
+        match self {
+            AccelerationDataRate::PowerOff => 0.0,
+            AccelerationDataRate::Hz3_125 => 3.125,
+            AccelerationDataRate::Hz6_25 => 6.25,
+            AccelerationDataRate::Hz12_5 => 12.5,
+            AccelerationDataRate::Hz25 => 25.0,
+            AccelerationDataRate::Hz50 => 50.0,
+            AccelerationDataRate::Hz100 => 100.0,
+            AccelerationDataRate::Hz200 => 200.0,
+            AccelerationDataRate::Hz400 => 400.0,
+            AccelerationDataRate::Hz800 => 800.0,
+            AccelerationDataRate::Hz1600 => 1600.0,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct AccelerometerConfiguration {
     pub axis_x: bool,
@@ -57,6 +130,18 @@ pub struct AccelerometerConfiguration {
 
     pub data_rate: AccelerationDataRate,
     pub scale: AccelerationFullScale,
+    pub bandwidth: AccelerationAntiAliasBandwidth,
+    pub high_pass_filter: Option<HighPassFilterConfiguration>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HighPassFilterConfiguration {
+    pub mode: HighPassFilterMode,
+    /// Route filtered data to the output registers.
+    pub filtered_data: bool,
+    pub click: bool,
+    pub interrupt_generator_1: bool,
+    pub interrupt_generator_2: bool,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -64,27 +149,33 @@ pub struct MagnetometerConfiguration {
     pub data_rate: MagnetometerDataRate,
     pub scale: MagnetometerFullScale,
     pub mode: MagneticSensorMode,
+    pub resolution: MagnetometerResolution,
+    /// Forces 3.125 Hz regardless of `data_rate` to save power.
+    pub low_power: bool,
 }
 
 pub struct InternalTemperatureConfiguration {
     pub active: bool,
 }
 
-pub struct LSM303D<I2C> {
-    i2c: I2C,
+pub struct LSM303D<T> {
+    transport: T,
     acc_divider: f64,
     mag_divider: f64,
-    address: u8,
+    acc_data_rate: AccelerationDataRate,
+    // Control registers saved by `power_down`, written back by `resume`
+    suspended: Option<lifecycle::SavedControl>,
+    overruns: OverrunCount,
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct AccelerometerMeasurements {
     pub x: f64,
     pub y: f64,
     pub z: f64,
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct MagnetometerMeasurements {
     pub x: f64,
     pub y: f64,
@@ -108,6 +199,37 @@ pub enum AccelerationFullScale {
     Acc16G = 0b0010_0000,
 }
 
+impl AccelerationFullScale {
+    /// Full scale range in g.
+    pub fn range(&self) -> f64 {
+        match self {
+            AccelerationFullScale::Acc2G => 2.0,
+            AccelerationFullScale::Acc4G => 4.0,
+            AccelerationFullScale::Acc6G => 6.0,
+            AccelerationFullScale::Acc8G => 8.0,
+            AccelerationFullScale::Acc16G => 16.0,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccelerationAntiAliasBandwidth {
+    Hz773 = 0b0000_0000,
+    Hz194 = 0b0100_0000,
+    Hz362 = 0b1000_0000,
+    Hz50  = 0b1100_0000,
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HighPassFilterMode {
+    NormalWithReset = 0b0000_0000, // Reset by reading the REFERENCE registers
+    Reference       = 0b0100_0000, // Output is relative to the REFERENCE registers
+    Normal          = 0b1000_0000,
+    AutoReset       = 0b1100_0000, // Reset on interrupt event
+}
+
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MagnetometerDataRate {
@@ -119,6 +241,8 @@ pub enum MagnetometerDataRate {
     Hz100   = 0b0001_0100,
 }
 
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MagnetometerResolution {
     Low  = 0b0000_0000,
     High = 0b0110_0000,
@@ -133,6 +257,18 @@ pub enum MagnetometerFullScale {
     Mag12Gauss = 0b0110_0000,
 }
 
+impl MagnetometerFullScale {
+    /// Full scale range in gauss.
+    pub fn range(&self) -> f64 {
+        match self {
+            MagnetometerFullScale::Mag2Gauss => 2.0,
+            MagnetometerFullScale::Mag4Gauss => 4.0,
+            MagnetometerFullScale::Mag8Gauss => 8.0,
+            MagnetometerFullScale::Mag12Gauss => 12.0,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MagneticSensorMode {
     ContinuousConversion = 0b0000_0000,
@@ -140,97 +276,195 @@ pub enum MagneticSensorMode {
     PowerDown            = 0b0000_0010,
 }
 
-impl<I2C> LSM303D<I2C>
-where I2C: WriteRead + Write + Read {
+impl<I2C> LSM303D<I2cTransport<I2C>>
+where I2C: WriteRead + Write {
+    /// Driver on the default address (SA0 high, 0x1D).
     pub fn new(i2c: I2C) -> Self {
+        Self::new_with_address(i2c, SlaveAddress::SA0High)
+    }
+
+    pub fn new_with_address(i2c: I2C, address: SlaveAddress) -> Self {
+        Self::with_transport(I2cTransport::new(i2c, address))
+    }
+}
+
+impl<SPI, CS> LSM303D<SpiTransport<SPI, CS>>
+where
+    SPI: spi::Transfer<u8> + spi::Write<u8>,
+    CS: OutputPin,
+{
+    pub fn new_spi(spi: SPI, cs: CS) -> Result<Self, ()> {
+        Ok(Self::with_transport(SpiTransport::new(spi, cs)?))
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Assumes the chip is in its power-on state, call `init` if it may have been
+    /// configured before.
+    pub fn with_transport(transport: T) -> Self {
         Self {
-            i2c,
-            mag_divider: 1.0,
-            acc_divider: 1.0,
-            address: ADDRESS,
+            transport,
+            // Power-on defaults
+            mag_divider: MagnetometerFullScale::Mag4Gauss.range(),
+            acc_divider: AccelerationFullScale::Acc2G.range(),
+            acc_data_rate: AccelerationDataRate::PowerOff,
+            suspended: None,
+            overruns: OverrunCount::default(),
         }
     }
 
+    pub fn release(self) -> T {
+        self.transport
+    }
+
+    /// Takes over the scaling and accelerometer data rate from the chip, e.g. after a
+    /// restart of the host while the sensor kept running. Unlike the
+    /// `read_*_configuration` methods this does not need a fully decodable configuration.
+    pub fn init(&mut self) -> Result<(), ()> {
+        let ctrl1: Ctrl1 = self.transport.read()?;
+        let ctrl2: Ctrl2 = self.transport.read()?;
+        let ctrl6: Ctrl6 = self.transport.read()?;
+
+        if let Some(data_rate) = ctrl1.data_rate() {
+            self.acc_data_rate = data_rate;
+        }
+        if let Some(scale) = ctrl2.scale() {
+            self.acc_divider = scale.range();
+        }
+        if let Some(scale) = ctrl6.scale() {
+            self.mag_divider = scale.range();
+        }
+
+        Ok(())
+    }
+
     pub fn check_connection(&mut self) -> Result<bool, ()> {
         let mut buffer = [0u8; 1];
-        self.i2c.write_read(self.address,&[Register::WhoAmI as u8], &mut buffer).map_err(|_| ())?;
+        self.transport.read_registers(Register::WhoAmI as u8, &mut buffer)?;
 
         Ok(buffer[0] == 0b01001001)
     }
 
     pub fn configure_internal_temperature(&mut self, configuration: InternalTemperatureConfiguration) -> Result<(), ()> {
-        let mut buffer = [0u8; 1];
-        self.i2c.write_read(self.address, &[Register::Ctrl5 as u8], &mut buffer).map_err(|_| ())?;
-        if configuration.active {
-            buffer[0] |= 0x80; 
-        } else {
-            buffer[0] &= !0x80; 
-        }
-        self.i2c.write(self.address, &[Register::Ctrl5 as u8, buffer[0]]).map_err(|_| ())?;
+        self.transport.modify(|ctrl5: &mut Ctrl5| {
+            ctrl5.set_temperature_enable(configuration.active);
+        })?;
 
         Ok(())
     }
 
     pub fn configure_accelerometer(&mut self, configuration: AccelerometerConfiguration) -> Result<(), ()> {
-        let mut buffer = [0u8; 1];
-        self.i2c
-            .write_read(
-                self.address, 
-                &[Register::Ctrl1 as u8],
-                &mut buffer
-            )
-            .map_err(|_| ())?;
-        cond_toggle_mask(configuration.axis_x, &mut buffer[0], 0x01);
-        cond_toggle_mask(configuration.axis_y, &mut buffer[0], 0x02);
-        cond_toggle_mask(configuration.axis_z, &mut buffer[0], 0x04);
-
-        buffer[0] &= 0b0000_1111; // Reset mask
-        buffer[0] |= configuration.data_rate as u8;
-
-        self.i2c.write(self.address, &[Register::Ctrl1 as u8, buffer[0]]).map_err(|_| ())?;
-
-        self.i2c.write_read(self.address, &[Register::Ctrl2 as u8], &mut buffer).map_err(|_| ())?;
-
-        buffer[0] &= 0b0011_1000; // Reset mask
-        buffer[0] |= configuration.scale as u8;
-        self.i2c.write(self.address, &[Register::Ctrl2 as u8, buffer[0]]).map_err(|_| ())?;
-
-        match configuration.scale {
-            AccelerationFullScale::Acc16G => self.acc_divider = 16.0,
-            AccelerationFullScale::Acc8G => self.acc_divider = 8.0,
-            AccelerationFullScale::Acc6G => self.acc_divider = 6.0,
-            AccelerationFullScale::Acc4G => self.acc_divider = 4.0,
-            AccelerationFullScale::Acc2G => self.acc_divider = 2.0,
-        }
+        self.transport.modify(|ctrl1: &mut Ctrl1| {
+            ctrl1
+                .set_axis_x(configuration.axis_x)
+                .set_axis_y(configuration.axis_y)
+                .set_axis_z(configuration.axis_z)
+                // Block data update until both bytes of a sample are read
+                .set_block_data_update(true)
+                .set_data_rate(configuration.data_rate);
+        })?;
+        self.acc_data_rate = configuration.data_rate;
+
+        self.transport.modify(|ctrl2: &mut Ctrl2| {
+            ctrl2
+                .set_self_test(false)
+                .set_bandwidth(configuration.bandwidth)
+                .set_scale(configuration.scale);
+        })?;
+
+        let filter = configuration.high_pass_filter;
+
+        self.transport.modify(|ctrl0: &mut Ctrl0| {
+            ctrl0
+                .set_high_pass_click(filter.is_some_and(|f| f.click))
+                .set_high_pass_interrupt_generator_1(filter.is_some_and(|f| f.interrupt_generator_1))
+                .set_high_pass_interrupt_generator_2(filter.is_some_and(|f| f.interrupt_generator_2));
+        })?;
+
+        self.transport.modify(|ctrl7: &mut Ctrl7| {
+            ctrl7
+                .set_high_pass_mode(filter.map(|f| f.mode).unwrap_or(HighPassFilterMode::NormalWithReset))
+                .set_filtered_data(filter.is_some_and(|f| f.filtered_data));
+        })?;
+
+        self.acc_divider = configuration.scale.range();
 
         Ok(())
     }
 
-    pub fn configure_magnetometer(&mut self, configuration: MagnetometerConfiguration) -> Result<(), ()> {
-        let mut buffer = [0u8; 1];
-        self.i2c.write_read(self.address, &[Register::Ctrl5 as u8], &mut buffer).map_err(|_| ())?;
+    /// Decodes CTRL0, CTRL1, CTRL2 and CTRL7 and updates the scaling used by
+    /// `read_measurements` to match what the chip holds.
+    pub fn read_accelerometer_configuration(&mut self) -> Result<AccelerometerConfiguration, ()> {
+        let ctrl0 = self.transport.read()?;
+        let ctrl1 = self.transport.read()?;
+        let ctrl2 = self.transport.read()?;
+        let ctrl7 = self.transport.read()?;
+
+        let configuration = decode_accelerometer_configuration(ctrl0, ctrl1, ctrl2, ctrl7).ok_or(())?;
+        self.acc_divider = configuration.scale.range();
+        self.acc_data_rate = configuration.data_rate;
 
-        buffer[0] &= 0b0001_1100; // Reset mask
-        buffer[0] |= configuration.data_rate as u8;
+        Ok(configuration)
+    }
+
+    /// Sets the reference used by `HighPassFilterMode::Reference`, in raw 8 bit units.
+    pub fn write_high_pass_reference(&mut self, x: i8, y: i8, z: i8) -> Result<(), ()> {
+        self.write_register(Register::ReferenceX, x as u8)?;
+        self.write_register(Register::ReferenceY, y as u8)?;
+        self.write_register(Register::ReferenceZ, z as u8)?;
+
+        Ok(())
+    }
 
-        self.i2c.write(self.address, &[Register::Ctrl5 as u8, buffer[0]]).map_err(|_| ())?;
+    pub fn configure_magnetometer(&mut self, configuration: MagnetometerConfiguration) -> Result<(), ()> {
+        self.transport.modify(|ctrl5: &mut Ctrl5| {
+            ctrl5
+                .set_resolution(configuration.resolution)
+                .set_data_rate(configuration.data_rate);
+        })?;
 
-        buffer[0] = configuration.scale as u8;
-        self.i2c.write(self.address, &[Register::Ctrl6 as u8, buffer[0]]).map_err(|_| ())?;
+        let mut ctrl6 = Ctrl6::reset();
+        ctrl6.set_scale(configuration.scale);
+        self.transport.write(ctrl6)?;
 
-        self.i2c.write_read(self.address, &[Register::Ctrl7 as u8], &mut buffer).map_err(|_| ())?;
-        buffer[0] &= 0b1111_1100;
-        buffer[0] |= configuration.mode as u8;
+        self.transport.modify(|ctrl7: &mut Ctrl7| {
+            ctrl7
+                .set_magnetometer_low_power(configuration.low_power)
+                .set_magnetometer_mode(configuration.mode);
+        })?;
 
-        self.i2c.write(self.address, &[Register::Ctrl7 as u8, buffer[0]]).map_err(|_| ())?;
+        self.mag_divider = configuration.scale.range();
 
-        match configuration.scale {
-            MagnetometerFullScale::Mag2Gauss => self.mag_divider = 2.0,
-            MagnetometerFullScale::Mag4Gauss => self.mag_divider = 4.0,
-            MagnetometerFullScale::Mag8Gauss => self.mag_divider = 8.0,
-            MagnetometerFullScale::Mag12Gauss => self.mag_divider = 12.0,
+        Ok(())
+    }
+
+    /// Decodes CTRL5, CTRL6 and CTRL7 and updates the scaling used by
+    /// `read_measurements` to match what the chip holds. Returns `None` while CTRL5 holds
+    /// the reserved data rate it has after power-on, i.e. before the magnetometer was
+    /// configured; the scaling is updated in that case as well.
+    pub fn read_magnetometer_configuration(&mut self) -> Result<Option<MagnetometerConfiguration>, ()> {
+        let ctrl5: Ctrl5 = self.transport.read()?;
+        let ctrl6: Ctrl6 = self.transport.read()?;
+        let ctrl7 = self.transport.read()?;
+
+        self.mag_divider = ctrl6.scale().ok_or(())?.range();
+        if ctrl5.data_rate().is_none() {
+            return Ok(None);
         }
 
+        decode_magnetometer_configuration(ctrl5, ctrl6, ctrl7).ok_or(()).map(Some)
+    }
+
+    /// Changes resolution and low-power mode without reconfiguring the magnetometer.
+    pub fn set_magnetometer_power_mode(&mut self, resolution: MagnetometerResolution, low_power: bool) -> Result<(), ()> {
+        self.transport.modify(|ctrl5: &mut Ctrl5| {
+            ctrl5.set_resolution(resolution);
+        })?;
+        self.transport.modify(|ctrl7: &mut Ctrl7| {
+            ctrl7.set_magnetometer_low_power(low_power);
+        })?;
+
         Ok(())
     }
 
@@ -240,36 +474,19 @@ where I2C: WriteRead + Write + Read {
         let acc_x;
         let acc_y;
         let acc_z;
-
-        let mag_x;
-        let mag_y;
-        let mag_z;
         let mut buffer = [0u8; 2];
 
-        self.i2c
-            .write_read(
-                self.address,
-                &[Register::TempOutL as u8 | 0x80],
-                &mut buffer
-            )
-            .map_err(|_| ())?;
+        self.transport.read_registers(Register::TempOutL as u8, &mut buffer)?;
 
         temperature = i16::from_le_bytes(buffer.try_into().unwrap());
 
-        self.i2c.write_read(self.address, &[Register::OutXLA as u8 | 0x80], &mut buffer).map_err(|_| ())?;
+        self.transport.read_registers(Register::OutXLA as u8, &mut buffer)?;
         acc_x = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.acc_divider) / 32678.0;
-        self.i2c.write_read(self.address, &[Register::OutYLA as u8 | 0x80], &mut buffer).map_err(|_| ())?;
+        self.transport.read_registers(Register::OutYLA as u8, &mut buffer)?;
         acc_y = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.acc_divider) / 32678.0;
-        self.i2c.write_read(self.address, &[Register::OutZLA as u8 | 0x80], &mut buffer).map_err(|_| ())?;
+        self.transport.read_registers(Register::OutZLA as u8, &mut buffer)?;
         acc_z = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.acc_divider) / 32678.0;
 
-        self.i2c.write_read(self.address, &[Register::OutXLM as u8 | 0x80], &mut buffer).map_err(|_| ())?;
-        mag_x = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.mag_divider) / 32678.0;
-        self.i2c.write_read(self.address, &[Register::OutYLM as u8 | 0x80], &mut buffer).map_err(|_| ())?;
-        mag_y = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.mag_divider) / 32678.0;
-        self.i2c.write_read(self.address, &[Register::OutZLM as u8 | 0x80], &mut buffer).map_err(|_| ())?;
-        mag_z = (f64::from(i16::from_le_bytes(buffer.try_into().unwrap())) * self.mag_divider) / 32678.0;
-
         Ok(Measurements {
             temperature: temperature.into(),
             accelerometer: AccelerometerMeasurements {
@@ -277,19 +494,87 @@ where I2C: WriteRead + Write + Read {
                 y: acc_y,
                 z: acc_z,
             },
-            magnetometer: MagnetometerMeasurements {
-                x: mag_x,
-                y: mag_y,
-                z: mag_z,
+            magnetometer: self.read_magnetometer()?,
+        })
+    }
+
+    /// Triggers a single magnetometer conversion and waits for it, polling STATUS_M
+    /// once per millisecond. Returns `Err` if no sample arrives within `timeout_ms`.
+    /// The magnetometer is powered down again by the chip once the sample is taken.
+    pub fn measure_magnetometer_once<D: DelayMs<u16>>(&mut self, delay: &mut D, timeout_ms: u16) -> Result<MagnetometerMeasurements, ()> {
+        // Reading the outputs clears a stale ZYXMDA left over from an earlier sample
+        if self.read_magnetometer_status()?.xyz_available {
+            self.read_magnetometer()?;
+        }
+
+        self.transport.modify(|ctrl7: &mut Ctrl7| {
+            ctrl7.set_magnetometer_mode(MagneticSensorMode::SingleConversion);
+        })?;
+
+        for _ in 0..timeout_ms {
+            delay.delay_ms(1);
+            if self.read_magnetometer_status()?.xyz_available {
+                return self.read_magnetometer();
             }
+        }
+
+        Err(())
+    }
+
+    fn read_magnetometer(&mut self) -> Result<MagnetometerMeasurements, ()> {
+        let mut buffer = [0u8; 6];
+        self.transport.read_registers(Register::OutXLM as u8, &mut buffer)?;
+
+        let axis = |low: usize| {
+            (f64::from(i16::from_le_bytes([buffer[low], buffer[low + 1]])) * self.mag_divider) / 32678.0
+        };
+
+        Ok(MagnetometerMeasurements {
+            x: axis(0),
+            y: axis(2),
+            z: axis(4),
         })
     }
+
+    fn write_register(&mut self, register: Register, value: u8) -> Result<(), ()> {
+        self.transport.write_register(register as u8, value)
+    }
 }
 
-fn cond_toggle_mask(condition: bool, value: &mut u8, mask: u8) {
-    if condition {
-        *value |= mask; 
+fn decode_accelerometer_configuration(ctrl0: Ctrl0, ctrl1: Ctrl1, ctrl2: Ctrl2, ctrl7: Ctrl7) -> Option<AccelerometerConfiguration> {
+    let high_pass_filter = if ctrl0.high_pass_click()
+        || ctrl0.high_pass_interrupt_generator_1()
+        || ctrl0.high_pass_interrupt_generator_2()
+        || ctrl7.filtered_data()
+    {
+        Some(HighPassFilterConfiguration {
+            mode: ctrl7.high_pass_mode()?,
+            filtered_data: ctrl7.filtered_data(),
+            click: ctrl0.high_pass_click(),
+            interrupt_generator_1: ctrl0.high_pass_interrupt_generator_1(),
+            interrupt_generator_2: ctrl0.high_pass_interrupt_generator_2(),
+        })
     } else {
-        *value &= !mask; 
-    }
+        None
+    };
+
+    Some(AccelerometerConfiguration {
+        axis_x: ctrl1.axis_x(),
+        axis_y: ctrl1.axis_y(),
+        axis_z: ctrl1.axis_z(),
+        data_rate: ctrl1.data_rate()?,
+        scale: ctrl2.scale()?,
+        bandwidth: ctrl2.bandwidth()?,
+        high_pass_filter,
+    })
+}
+
+fn decode_magnetometer_configuration(ctrl5: Ctrl5, ctrl6: Ctrl6, ctrl7: Ctrl7) -> Option<MagnetometerConfiguration> {
+    Some(MagnetometerConfiguration {
+        data_rate: ctrl5.data_rate()?,
+        scale: ctrl6.scale()?,
+        mode: ctrl7.magnetometer_mode()?,
+        resolution: ctrl5.resolution()?,
+        low_power: ctrl7.magnetometer_low_power(),
+    })
 }
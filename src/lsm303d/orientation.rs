@@ -0,0 +1,197 @@
+use libm::{acos, sqrt};
+
+use super::{LSM303D, AccelerometerMeasurements};
+use super::click::{g_to_threshold, ms_to_samples};
+use super::registers::{Ctrl3, Ctrl5, IgCfg1, IgDur1, IgSrc1, IgThs1, IntCtrlM};
+use super::transport::Transport;
+use crate::register::{Register as _, RegisterAccess};
+
+/// Position of the device, named after the axis pointing up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    /// +Y up
+    PortraitUp,
+    /// -Y up
+    PortraitDown,
+    /// +X up
+    LandscapeLeft,
+    /// -X up
+    LandscapeRight,
+    /// +Z up
+    FaceUp,
+    /// -Z up
+    FaceDown,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 6] = [
+        Orientation::PortraitUp,
+        Orientation::PortraitDown,
+        Orientation::LandscapeLeft,
+        Orientation::LandscapeRight,
+        Orientation::FaceUp,
+        Orientation::FaceDown,
+    ];
+
+    fn is_flat(&self) -> bool {
+        matches!(self, Orientation::FaceUp | Orientation::FaceDown)
+    }
+
+    /// Component of a unit vector along the axis pointing up in this orientation.
+    fn component(&self, x: f64, y: f64, z: f64) -> f64 {
+        match self {
+            Orientation::PortraitUp => y,
+            Orientation::PortraitDown => -y,
+            Orientation::LandscapeLeft => x,
+            Orientation::LandscapeRight => -x,
+            Orientation::FaceUp => z,
+            Orientation::FaceDown => -z,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OrientationConfiguration {
+    /// Only recognize the four portrait and landscape positions, ignoring Z.
+    pub four_d: bool,
+    /// Acceleration in g an axis has to exceed, limited by the configured full scale.
+    /// Around 0.7 g places the boundary near 45 degrees.
+    pub threshold: f64,
+    /// Time the new position has to be held before it is reported.
+    pub duration_ms: f64,
+    /// Keep the interrupt active until IG_SRC1 is read.
+    pub latch: bool,
+    pub int1: bool,
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Sets up interrupt generator 1 for 6D (or 4D) position recognition. Timings and
+    /// threshold are converted using the last `configure_accelerometer` call, so the
+    /// accelerometer has to be configured first.
+    pub fn configure_orientation_detection(&mut self, configuration: OrientationConfiguration) -> Result<(), ()> {
+        let frequency = self.acc_data_rate.frequency();
+        if frequency == 0.0 {
+            return Err(());
+        }
+
+        let mut threshold = IgThs1::reset();
+        threshold.set_threshold(g_to_threshold(configuration.threshold, self.acc_divider));
+        let mut duration = IgDur1::reset();
+        duration.set_duration(ms_to_samples(configuration.duration_ms, frequency).min(0x7F));
+
+        let mut cfg = IgCfg1::reset();
+        cfg
+            .set_and_combination(true)
+            .set_detection_6d(true)
+            .set_x_high(true)
+            .set_x_low(true)
+            .set_y_high(true)
+            .set_y_low(true)
+            .set_z_high(!configuration.four_d)
+            .set_z_low(!configuration.four_d);
+
+        self.transport.write(threshold)?;
+        self.transport.write(duration)?;
+        self.transport.modify(|int_ctrl: &mut IntCtrlM| {
+            int_ctrl.set_detection_4d(configuration.four_d);
+        })?;
+        self.transport.modify(|ctrl5: &mut Ctrl5| {
+            ctrl5.set_latch_interrupt_1(configuration.latch);
+        })?;
+        self.transport.modify(|ctrl3: &mut Ctrl3| {
+            ctrl3.set_int1_interrupt_generator_1(configuration.int1);
+        })?;
+        self.transport.write(cfg)?;
+
+        Ok(())
+    }
+
+    /// Reads the position recognized by interrupt generator 1, `None` while the device is
+    /// between positions. Reading IG_SRC1 also clears a latched interrupt.
+    pub fn read_orientation(&mut self) -> Result<Option<Orientation>, ()> {
+        let source: IgSrc1 = self.transport.read()?;
+        if !source.active() {
+            return Ok(None);
+        }
+
+        Ok(if source.z_high() {
+            Some(Orientation::FaceUp)
+        } else if source.z_low() {
+            Some(Orientation::FaceDown)
+        } else if source.y_high() {
+            Some(Orientation::PortraitUp)
+        } else if source.y_low() {
+            Some(Orientation::PortraitDown)
+        } else if source.x_high() {
+            Some(Orientation::LandscapeLeft)
+        } else if source.x_low() {
+            Some(Orientation::LandscapeRight)
+        } else {
+            None
+        })
+    }
+}
+
+/// Orientation computed from accelerometer samples, for when the interrupt generators
+/// are busy or the accelerometer is read anyway.
+pub struct OrientationDetector {
+    four_d: bool,
+    hysteresis: f64,
+    current: Option<Orientation>,
+}
+
+impl OrientationDetector {
+    /// `hysteresis` is the angle in degrees the device has to be tilted past the 45 degree
+    /// boundary before a new orientation is reported.
+    pub fn new(four_d: bool, hysteresis: f64) -> Self {
+        Self {
+            four_d,
+            hysteresis: hysteresis.to_radians(),
+            current: None,
+        }
+    }
+
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.current
+    }
+
+    /// Feeds a new sample and returns the resulting orientation. Samples without a usable
+    /// direction, like free fall or a flat device in 4D mode, keep the last orientation.
+    pub fn update(&mut self, measurements: &AccelerometerMeasurements) -> Option<Orientation> {
+        let z = if self.four_d { 0.0 } else { measurements.z };
+        let length = sqrt(measurements.x * measurements.x + measurements.y * measurements.y + z * z);
+        if length < 0.1 {
+            return self.current;
+        }
+        let (x, y, z) = (measurements.x / length, measurements.y / length, z / length);
+
+        let angle = |orientation: Orientation| acos(orientation.component(x, y, z).clamp(-1.0, 1.0));
+
+        let four_d = self.four_d;
+        let mut best = None;
+        let mut best_angle = core::f64::consts::PI;
+        for orientation in Orientation::ALL.iter().filter(|o| !(four_d && o.is_flat())) {
+            let candidate = angle(*orientation);
+            if candidate < best_angle {
+                best = Some(*orientation);
+                best_angle = candidate;
+            }
+        }
+
+        self.current = match (self.current, best) {
+            (Some(current), Some(best)) if current != best => {
+                // Equal angles mean the sample lies on the boundary between both positions
+                if angle(current) - best_angle >= 2.0 * self.hysteresis {
+                    Some(best)
+                } else {
+                    Some(current)
+                }
+            }
+            (None, best) => best,
+            (current, _) => current,
+        };
+
+        self.current
+    }
+}
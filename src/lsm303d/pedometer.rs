@@ -0,0 +1,280 @@
+use libm::{exp, sqrt};
+
+use super::{AccelerometerMeasurements, AccelerationDataRate};
+
+// Time constant of the gravity estimate subtracted from the magnitude.
+const GRAVITY_TIME_CONSTANT: f64 = 1.0;
+// Time constant of the low pass removing jitter from the step signal.
+const SMOOTHING_TIME_CONSTANT: f64 = 0.05;
+// Time constant with which the peak and valley envelopes fall back to the signal.
+const ENVELOPE_TIME_CONSTANT: f64 = 1.5;
+// Peak to valley swing in g below which the signal is considered noise.
+const MIN_AMPLITUDE: f64 = 0.1;
+// Shortest accepted step interval, 240 steps per minute.
+const MIN_STEP_INTERVAL: f64 = 0.25;
+// Longest pause between steps that still belongs to the same walk.
+const MAX_STEP_INTERVAL: f64 = 2.0;
+// Cadence in steps per minute from which on the activity counts as running.
+const RUNNING_CADENCE: f64 = 140.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ActivityState {
+    Still,
+    Walking,
+    Running,
+}
+
+/// Step detector working on the acceleration magnitude, so it does not depend on how
+/// the sensor is worn.
+///
+/// The magnitude has gravity removed and is smoothed. A step is counted when the signal
+/// rises through the middle of its recent peak and valley, as long as the swing is large
+/// enough and the step is not closer than a plausible step interval to the previous one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pedometer {
+    sample_period: f64,
+    gravity_gain: f64,
+    smoothing_gain: f64,
+    envelope_decay: f64,
+
+    gravity: Option<f64>,
+    signal: f64,
+    peak: f64,
+    valley: f64,
+
+    samples: u32,
+    last_step: Option<u32>,
+    interval: Option<f64>,
+    steps: u32,
+}
+
+impl Pedometer {
+    /// Detector for samples taken every `sample_period` seconds, e.g. by a logger that
+    /// polls at its own pace instead of the data rate. Returns `Err` unless the period is
+    /// positive and finite.
+    pub fn new(sample_period: f64) -> Result<Self, ()> {
+        if !(sample_period > 0.0 && sample_period.is_finite()) {
+            return Err(());
+        }
+
+        Ok(Self {
+            sample_period,
+            gravity_gain: low_pass_gain(sample_period, GRAVITY_TIME_CONSTANT),
+            smoothing_gain: low_pass_gain(sample_period, SMOOTHING_TIME_CONSTANT),
+            envelope_decay: 1.0 - low_pass_gain(sample_period, ENVELOPE_TIME_CONSTANT),
+
+            gravity: None,
+            signal: 0.0,
+            peak: 0.0,
+            valley: 0.0,
+
+            samples: 0,
+            last_step: None,
+            interval: None,
+            steps: 0,
+        })
+    }
+
+    /// Detector for samples read at the output data rate of the accelerometer. Returns
+    /// `Err` for `AccelerationDataRate::PowerOff`.
+    pub fn from_data_rate(data_rate: AccelerationDataRate) -> Result<Self, ()> {
+        let frequency = data_rate.frequency();
+        if frequency == 0.0 {
+            return Err(());
+        }
+
+        Self::new(1.0 / frequency)
+    }
+
+    /// Feeds the next sample. Returns `true` when it completes a step.
+    pub fn update(&mut self, measurements: &AccelerometerMeasurements) -> bool {
+        let magnitude = sqrt(
+            measurements.x * measurements.x + measurements.y * measurements.y + measurements.z * measurements.z
+        );
+        self.samples = self.samples.wrapping_add(1);
+
+        let gravity = match self.gravity {
+            Some(gravity) => gravity + self.gravity_gain * (magnitude - gravity),
+            None => magnitude,
+        };
+        self.gravity = Some(gravity);
+
+        let previous = self.signal;
+        self.signal += self.smoothing_gain * (magnitude - gravity - self.signal);
+
+        // Envelopes jump to new extremes and slowly decay towards the signal otherwise
+        self.peak = if self.signal > self.peak { self.signal } else { self.signal + (self.peak - self.signal) * self.envelope_decay };
+        self.valley = if self.signal < self.valley { self.signal } else { self.signal + (self.valley - self.signal) * self.envelope_decay };
+
+        if self.seconds_since_step().is_some_and(|elapsed| elapsed > MAX_STEP_INTERVAL) {
+            self.last_step = None;
+            self.interval = None;
+        }
+
+        let threshold = (self.peak + self.valley) / 2.0;
+        let crossed = previous < threshold && self.signal >= threshold;
+        if !crossed || self.peak - self.valley < MIN_AMPLITUDE {
+            return false;
+        }
+
+        match self.seconds_since_step() {
+            Some(elapsed) if elapsed < MIN_STEP_INTERVAL => return false,
+            Some(elapsed) => {
+                self.interval = Some(match self.interval {
+                    Some(interval) => interval + 0.3 * (elapsed - interval),
+                    None => elapsed,
+                });
+            }
+            None => {}
+        }
+
+        self.last_step = Some(self.samples);
+        self.steps = self.steps.saturating_add(1);
+
+        true
+    }
+
+    pub fn step_count(&self) -> u32 {
+        self.steps
+    }
+
+    /// Steps per minute, averaged over the last few steps. Zero when not walking.
+    pub fn cadence(&self) -> f64 {
+        match self.interval {
+            Some(interval) if self.last_step.is_some() => 60.0 / interval,
+            _ => 0.0,
+        }
+    }
+
+    pub fn activity(&self) -> ActivityState {
+        let cadence = self.cadence();
+        if cadence == 0.0 {
+            ActivityState::Still
+        } else if cadence >= RUNNING_CADENCE {
+            ActivityState::Running
+        } else {
+            ActivityState::Walking
+        }
+    }
+
+    /// Clears the step count and all filter state.
+    pub fn reset(&mut self) {
+        self.gravity = None;
+        self.signal = 0.0;
+        self.peak = 0.0;
+        self.valley = 0.0;
+        self.samples = 0;
+        self.last_step = None;
+        self.interval = None;
+        self.steps = 0;
+    }
+
+    fn seconds_since_step(&self) -> Option<f64> {
+        self.last_step
+            .map(|step| f64::from(self.samples.wrapping_sub(step)) * self.sample_period)
+    }
+}
+
+/// Gain of a single pole low pass with the given time constant.
+fn low_pass_gain(sample_period: f64, time_constant: f64) -> f64 {
+    1.0 - exp(-sample_period / time_constant)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::PI;
+
+    use libm::sin;
+
+    use super::*;
+
+    // 3 s standing, 20 s walking at 1.8 steps per second, 5 s standing, in the format
+    // written by the logger at its roughly 10 Hz polling rate
+    static WALK_TRACE: &str = include_str!("../../testdata/pedometer_walk_10hz.csv");
+
+    fn trace() -> impl Iterator<Item = (u64, AccelerometerMeasurements)> {
+        WALK_TRACE.lines().map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let mut next = || fields.next().unwrap();
+            let timestamp = next().parse().unwrap();
+            let (x, y, z) = (next().parse().unwrap(), next().parse().unwrap(), next().parse().unwrap());
+            (timestamp, AccelerometerMeasurements { x, y, z })
+        })
+    }
+
+    /// Vertical bounce at `steps_per_second` on top of gravity.
+    fn walk(pedometer: &mut Pedometer, steps_per_second: f64, seconds: f64) {
+        let samples = (seconds / pedometer.sample_period) as u32;
+        for sample in 0..samples {
+            let time = f64::from(sample) * pedometer.sample_period;
+            let z = 1.0 + 0.4 * sin(2.0 * PI * steps_per_second * time);
+            pedometer.update(&AccelerometerMeasurements { x: 0.0, y: 0.0, z });
+        }
+    }
+
+    #[test]
+    fn counts_steps_of_recorded_trace() {
+        let (first, _) = trace().next().unwrap();
+        let (last, _) = trace().last().unwrap();
+        let sample_period = (last - first) as f64 / 1000.0 / (trace().count() - 1) as f64;
+        let mut pedometer = Pedometer::new(sample_period).unwrap();
+
+        let mut walking = None;
+        for (timestamp, measurements) in trace() {
+            pedometer.update(&measurements);
+            if timestamp < 23_000 {
+                walking = Some((pedometer.cadence(), pedometer.activity()));
+            }
+        }
+
+        // 36 strides, the end of the last one may count as a step of its own
+        assert!((36..=37).contains(&pedometer.step_count()), "{} steps", pedometer.step_count());
+        let (cadence, activity) = walking.unwrap();
+        assert!((cadence - 108.0).abs() < 5.0, "cadence {cadence}");
+        assert_eq!(activity, ActivityState::Walking);
+
+        assert_eq!(pedometer.cadence(), 0.0);
+        assert_eq!(pedometer.activity(), ActivityState::Still);
+    }
+
+    #[test]
+    fn detects_running() {
+        let mut pedometer = Pedometer::from_data_rate(AccelerationDataRate::Hz50).unwrap();
+        walk(&mut pedometer, 2.8, 10.0);
+
+        assert!((27..=28).contains(&pedometer.step_count()), "{} steps", pedometer.step_count());
+        assert!((pedometer.cadence() - 168.0).abs() < 5.0, "cadence {}", pedometer.cadence());
+        assert_eq!(pedometer.activity(), ActivityState::Running);
+    }
+
+    #[test]
+    fn ignores_noise_while_standing() {
+        let mut pedometer = Pedometer::from_data_rate(AccelerationDataRate::Hz50).unwrap();
+        for sample in 0..500 {
+            let z = if sample % 2 == 0 { 1.02 } else { 0.98 };
+            pedometer.update(&AccelerometerMeasurements { x: 0.0, y: 0.0, z });
+        }
+
+        assert_eq!(pedometer.step_count(), 0);
+        assert_eq!(pedometer.activity(), ActivityState::Still);
+    }
+
+    #[test]
+    fn reset_clears_steps() {
+        let mut pedometer = Pedometer::from_data_rate(AccelerationDataRate::Hz50).unwrap();
+        walk(&mut pedometer, 1.8, 5.0);
+        assert!(pedometer.step_count() > 0);
+
+        pedometer.reset();
+        assert_eq!(pedometer.step_count(), 0);
+        assert_eq!(pedometer.activity(), ActivityState::Still);
+    }
+
+    #[test]
+    fn rejects_invalid_sample_period() {
+        assert!(Pedometer::new(0.0).is_err());
+        assert!(Pedometer::new(-0.1).is_err());
+        assert!(Pedometer::new(f64::NAN).is_err());
+        assert!(Pedometer::from_data_rate(AccelerationDataRate::PowerOff).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::register::{register, Field};
+
+use super::{
+    AccelerationAntiAliasBandwidth,
+    AccelerationDataRate,
+    AccelerationFullScale,
+    HighPassFilterMode,
+    MagneticSensorMode,
+    MagnetometerDataRate,
+    MagnetometerFullScale,
+    MagnetometerResolution,
+};
+
+register! {
+    StatusM = 0x07, reset 0x00 {
+        flag xyz_overrun, set_xyz_overrun: 7;
+        flag z_overrun, set_z_overrun: 6;
+        flag y_overrun, set_y_overrun: 5;
+        flag x_overrun, set_x_overrun: 4;
+        flag xyz_available, set_xyz_available: 3;
+        flag z_available, set_z_available: 2;
+        flag y_available, set_y_available: 1;
+        flag x_available, set_x_available: 0;
+    }
+}
+
+register! {
+    IntCtrlM = 0x12, reset 0xE8 {
+        flag axis_x, set_axis_x: 7;
+        flag axis_y, set_axis_y: 6;
+        flag axis_z, set_axis_z: 5;
+        flag open_drain, set_open_drain: 4;
+        flag active_high, set_active_high: 3;
+        // Cleared means the request is latched until INT_SRC_M is read
+        flag not_latched, set_not_latched: 2;
+        flag detection_4d, set_detection_4d: 1;
+        flag enable, set_enable: 0;
+    }
+}
+
+register! {
+    IntSrcM = 0x13, reset 0x00 {
+        flag positive_x, set_positive_x: 7;
+        flag positive_y, set_positive_y: 6;
+        flag positive_z, set_positive_z: 5;
+        flag negative_x, set_negative_x: 4;
+        flag negative_y, set_negative_y: 3;
+        flag negative_z, set_negative_z: 2;
+        flag overflow, set_overflow: 1;
+        flag active, set_active: 0;
+    }
+}
+
+register! {
+    Ctrl0 = 0x1F, reset 0x00 {
+        flag boot, set_boot: 7;
+        flag fifo_enable, set_fifo_enable: 6;
+        flag fifo_threshold_enable, set_fifo_threshold_enable: 5;
+        flag high_pass_click, set_high_pass_click: 2;
+        flag high_pass_interrupt_generator_1, set_high_pass_interrupt_generator_1: 1;
+        flag high_pass_interrupt_generator_2, set_high_pass_interrupt_generator_2: 0;
+    }
+}
+
+register! {
+    Ctrl1 = 0x20, reset 0x07 {
+        flag block_data_update, set_block_data_update: 3;
+        flag axis_z, set_axis_z: 2;
+        flag axis_y, set_axis_y: 1;
+        flag axis_x, set_axis_x: 0;
+        field data_rate, set_data_rate: AccelerationDataRate = 0b1111_0000;
+    }
+}
+
+register! {
+    Ctrl2 = 0x21, reset 0x00 {
+        flag self_test, set_self_test: 1;
+        flag spi_3_wire, set_spi_3_wire: 0;
+        field bandwidth, set_bandwidth: AccelerationAntiAliasBandwidth = 0b1100_0000;
+        field scale, set_scale: AccelerationFullScale = 0b0011_1000;
+    }
+}
+
+register! {
+    Ctrl3 = 0x22, reset 0x00 {
+        flag int1_boot, set_int1_boot: 7;
+        flag int1_click, set_int1_click: 6;
+        flag int1_interrupt_generator_1, set_int1_interrupt_generator_1: 5;
+        flag int1_interrupt_generator_2, set_int1_interrupt_generator_2: 4;
+        flag int1_magnetometer, set_int1_magnetometer: 3;
+        flag int1_accelerometer_ready, set_int1_accelerometer_ready: 2;
+        flag int1_magnetometer_ready, set_int1_magnetometer_ready: 1;
+        flag int1_fifo_empty, set_int1_fifo_empty: 0;
+    }
+}
+
+register! {
+    Ctrl4 = 0x23, reset 0x00 {
+        flag int2_click, set_int2_click: 7;
+        flag int2_interrupt_generator_1, set_int2_interrupt_generator_1: 6;
+        flag int2_interrupt_generator_2, set_int2_interrupt_generator_2: 5;
+        flag int2_magnetometer, set_int2_magnetometer: 4;
+        flag int2_accelerometer_ready, set_int2_accelerometer_ready: 3;
+        flag int2_magnetometer_ready, set_int2_magnetometer_ready: 2;
+        flag int2_fifo_overrun, set_int2_fifo_overrun: 1;
+        flag int2_fifo_threshold, set_int2_fifo_threshold: 0;
+    }
+}
+
+register! {
+    Ctrl5 = 0x24, reset 0x18 {
+        flag temperature_enable, set_temperature_enable: 7;
+        flag latch_interrupt_2, set_latch_interrupt_2: 1;
+        flag latch_interrupt_1, set_latch_interrupt_1: 0;
+        field resolution, set_resolution: MagnetometerResolution = 0b0110_0000;
+        field data_rate, set_data_rate: MagnetometerDataRate = 0b0001_1100;
+    }
+}
+
+register! {
+    Ctrl6 = 0x25, reset 0x20 {
+        field scale, set_scale: MagnetometerFullScale = 0b0110_0000;
+    }
+}
+
+register! {
+    Ctrl7 = 0x26, reset 0x02 {
+        flag filtered_data, set_filtered_data: 5;
+        flag temperature_only, set_temperature_only: 4;
+        flag magnetometer_low_power, set_magnetometer_low_power: 2;
+        field high_pass_mode, set_high_pass_mode: HighPassFilterMode = 0b1100_0000;
+        field magnetometer_mode, set_magnetometer_mode: MagneticSensorMode = 0b0000_0011;
+    }
+}
+
+register! {
+    StatusA = 0x27, reset 0x00 {
+        flag xyz_overrun, set_xyz_overrun: 7;
+        flag z_overrun, set_z_overrun: 6;
+        flag y_overrun, set_y_overrun: 5;
+        flag x_overrun, set_x_overrun: 4;
+        flag xyz_available, set_xyz_available: 3;
+        flag z_available, set_z_available: 2;
+        flag y_available, set_y_available: 1;
+        flag x_available, set_x_available: 0;
+    }
+}
+
+register! {
+    IgCfg1 = 0x30, reset 0x00 {
+        // AOI and 6D together select the position recognition mode
+        flag and_combination, set_and_combination: 7;
+        flag detection_6d, set_detection_6d: 6;
+        flag z_high, set_z_high: 5;
+        flag z_low, set_z_low: 4;
+        flag y_high, set_y_high: 3;
+        flag y_low, set_y_low: 2;
+        flag x_high, set_x_high: 1;
+        flag x_low, set_x_low: 0;
+    }
+}
+
+register! {
+    IgSrc1 = 0x31, reset 0x00 {
+        flag active, set_active: 6;
+        flag z_high, set_z_high: 5;
+        flag z_low, set_z_low: 4;
+        flag y_high, set_y_high: 3;
+        flag y_low, set_y_low: 2;
+        flag x_high, set_x_high: 1;
+        flag x_low, set_x_low: 0;
+    }
+}
+
+register! {
+    IgThs1 = 0x32, reset 0x00 {
+        field threshold, set_threshold: u8 = 0b0111_1111;
+    }
+}
+
+register! {
+    IgDur1 = 0x33, reset 0x00 {
+        field duration, set_duration: u8 = 0b0111_1111;
+    }
+}
+
+register! {
+    ClickCfg = 0x38, reset 0x00 {
+        flag z_double, set_z_double: 5;
+        flag z_single, set_z_single: 4;
+        flag y_double, set_y_double: 3;
+        flag y_single, set_y_single: 2;
+        flag x_double, set_x_double: 1;
+        flag x_single, set_x_single: 0;
+    }
+}
+
+register! {
+    ClickSrc = 0x39, reset 0x00 {
+        flag active, set_active: 6;
+        flag double, set_double: 5;
+        flag single, set_single: 4;
+        flag negative, set_negative: 3;
+        flag z, set_z: 2;
+        flag y, set_y: 1;
+        flag x, set_x: 0;
+    }
+}
+
+register! {
+    ClickThs = 0x3A, reset 0x00 {
+        field threshold, set_threshold: u8 = 0b0111_1111;
+    }
+}
+
+register! {
+    TimeLimit = 0x3B, reset 0x00 {
+        field limit, set_limit: u8 = 0b0111_1111;
+    }
+}
+
+register! {
+    TimeLatency = 0x3C, reset 0x00 {
+        field latency, set_latency: u8 = 0b1111_1111;
+    }
+}
+
+register! {
+    TimeWindow = 0x3D, reset 0x00 {
+        field window, set_window: u8 = 0b1111_1111;
+    }
+}
+
+impl Field for AccelerationDataRate {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => AccelerationDataRate::PowerOff,
+            0b0001_0000 => AccelerationDataRate::Hz3_125,
+            0b0010_0000 => AccelerationDataRate::Hz6_25,
+            0b0011_0000 => AccelerationDataRate::Hz12_5,
+            0b0100_0000 => AccelerationDataRate::Hz25,
+            0b0101_0000 => AccelerationDataRate::Hz50,
+            0b0110_0000 => AccelerationDataRate::Hz100,
+            0b0111_0000 => AccelerationDataRate::Hz200,
+            0b1000_0000 => AccelerationDataRate::Hz400,
+            0b1001_0000 => AccelerationDataRate::Hz800,
+            0b1010_0000 => AccelerationDataRate::Hz1600,
+            _ => return None,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for AccelerationFullScale {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => AccelerationFullScale::Acc2G,
+            0b0000_1000 => AccelerationFullScale::Acc4G,
+            0b0001_0000 => AccelerationFullScale::Acc6G,
+            0b0001_1000 => AccelerationFullScale::Acc8G,
+            0b0010_0000 => AccelerationFullScale::Acc16G,
+            _ => return None,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for AccelerationAntiAliasBandwidth {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => AccelerationAntiAliasBandwidth::Hz773,
+            0b0100_0000 => AccelerationAntiAliasBandwidth::Hz194,
+            0b1000_0000 => AccelerationAntiAliasBandwidth::Hz362,
+            _ => AccelerationAntiAliasBandwidth::Hz50,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for HighPassFilterMode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => HighPassFilterMode::NormalWithReset,
+            0b0100_0000 => HighPassFilterMode::Reference,
+            0b1000_0000 => HighPassFilterMode::Normal,
+            _ => HighPassFilterMode::AutoReset,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for MagnetometerDataRate {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => MagnetometerDataRate::Hz3_125,
+            0b0000_0100 => MagnetometerDataRate::Hz6_25,
+            0b0000_1000 => MagnetometerDataRate::Hz12_5,
+            0b0000_1100 => MagnetometerDataRate::Hz25,
+            0b0001_0000 => MagnetometerDataRate::Hz50,
+            0b0001_0100 => MagnetometerDataRate::Hz100,
+            // 0b110 after power-on and 0b111 are reserved
+            _ => return None,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for MagnetometerResolution {
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0b0000_0000 => Some(MagnetometerResolution::Low),
+            0b0110_0000 => Some(MagnetometerResolution::High),
+            _ => None,
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for MagnetometerFullScale {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => MagnetometerFullScale::Mag2Gauss,
+            0b0010_0000 => MagnetometerFullScale::Mag4Gauss,
+            0b0100_0000 => MagnetometerFullScale::Mag8Gauss,
+            _ => MagnetometerFullScale::Mag12Gauss,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Field for MagneticSensorMode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b0000_0000 => MagneticSensorMode::ContinuousConversion,
+            0b0000_0001 => MagneticSensorMode::SingleConversion,
+            _ => MagneticSensorMode::PowerDown,
+        })
+    }
+
+    fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
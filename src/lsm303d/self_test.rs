@@ -0,0 +1,110 @@
+use embedded_hal::blocking::delay::DelayMs;
+use libm::fabs;
+
+use super::{LSM303D, AccelerationDataRate, AccelerationFullScale, AccelerometerMeasurements};
+use super::registers::{Ctrl1, Ctrl2};
+use super::transport::Transport;
+use crate::register::RegisterAccess;
+
+// Datasheet limits of the self-test output change in the ±2 g range, in mg.
+const SELF_TEST_MIN_MG: f64 = 70.0;
+const SELF_TEST_MAX_MG: f64 = 1700.0;
+
+const SELF_TEST_SAMPLES: u16 = 5;
+const SETTLE_TIME_MS: u16 = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SelfTestResult {
+    pub axis_x: bool,
+    pub axis_y: bool,
+    pub axis_z: bool,
+    /// Output change caused by the self-test actuation, in mg.
+    pub difference: AccelerometerMeasurements,
+}
+
+impl SelfTestResult {
+    pub fn passed(&self) -> bool {
+        self.axis_x && self.axis_y && self.axis_z
+    }
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    /// Runs the accelerometer self-test at 50 Hz and ±2 g and compares the output change
+    /// against the datasheet limits. The device must be kept still while it runs.
+    /// The previous accelerometer configuration is restored afterwards.
+    pub fn self_test<D: DelayMs<u16>>(&mut self, delay: &mut D) -> Result<SelfTestResult, ()> {
+        let ctrl1: Ctrl1 = self.transport.read()?;
+        let ctrl2: Ctrl2 = self.transport.read()?;
+        let acc_divider = self.acc_divider;
+
+        let mut test_ctrl1 = ctrl1;
+        test_ctrl1
+            .set_axis_x(true)
+            .set_axis_y(true)
+            .set_axis_z(true)
+            .set_data_rate(AccelerationDataRate::Hz50);
+        let mut test_ctrl2 = ctrl2;
+        test_ctrl2.set_scale(AccelerationFullScale::Acc2G);
+
+        self.transport.write(test_ctrl1)?;
+        self.transport.write(test_ctrl2)?;
+        self.acc_divider = AccelerationFullScale::Acc2G.range();
+
+        let result = self.self_test_measure(delay, test_ctrl2);
+
+        self.transport.write(ctrl2)?;
+        self.transport.write(ctrl1)?;
+        self.acc_divider = acc_divider;
+
+        let (normal, actuated) = result?;
+        let difference = AccelerometerMeasurements {
+            x: (actuated.x - normal.x) * 1000.0,
+            y: (actuated.y - normal.y) * 1000.0,
+            z: (actuated.z - normal.z) * 1000.0,
+        };
+        let within_limits = |value: f64| (SELF_TEST_MIN_MG..=SELF_TEST_MAX_MG).contains(&fabs(value));
+
+        Ok(SelfTestResult {
+            axis_x: within_limits(difference.x),
+            axis_y: within_limits(difference.y),
+            axis_z: within_limits(difference.z),
+            difference,
+        })
+    }
+
+    fn self_test_measure<D: DelayMs<u16>>(
+        &mut self,
+        delay: &mut D,
+        mut ctrl2: Ctrl2,
+    ) -> Result<(AccelerometerMeasurements, AccelerometerMeasurements), ()> {
+        delay.delay_ms(SETTLE_TIME_MS);
+        let normal = self.average_acceleration(delay)?;
+
+        ctrl2.set_self_test(true);
+        self.transport.write(ctrl2)?;
+        delay.delay_ms(SETTLE_TIME_MS);
+        let actuated = self.average_acceleration(delay)?;
+
+        Ok((normal, actuated))
+    }
+
+    fn average_acceleration<D: DelayMs<u16>>(&mut self, delay: &mut D) -> Result<AccelerometerMeasurements, ()> {
+        let mut sum = AccelerometerMeasurements::default();
+        for _ in 0..SELF_TEST_SAMPLES {
+            // One sample period at 50 Hz
+            delay.delay_ms(20);
+            let acc = self.read_measurements()?.accelerometer;
+            sum.x += acc.x;
+            sum.y += acc.y;
+            sum.z += acc.z;
+        }
+
+        let count = f64::from(SELF_TEST_SAMPLES);
+        Ok(AccelerometerMeasurements {
+            x: sum.x / count,
+            y: sum.y / count,
+            z: sum.z / count,
+        })
+    }
+}
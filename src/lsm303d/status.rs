@@ -0,0 +1,122 @@
+use embedded_hal::blocking::delay::DelayMs;
+
+use super::{LSM303D, Measurements};
+use super::registers::{StatusA, StatusM};
+use super::transport::Transport;
+use crate::register::RegisterAccess;
+
+/// Decoded STATUS_A or STATUS_M register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub xyz_overrun: bool,
+    pub z_overrun: bool,
+    pub y_overrun: bool,
+    pub x_overrun: bool,
+    pub xyz_available: bool,
+    pub z_available: bool,
+    pub y_available: bool,
+    pub x_available: bool,
+}
+
+// STATUS_A and STATUS_M share their layout
+macro_rules! status_from_register {
+    ($($register:ty),*) => {
+        $(
+            impl From<$register> for Status {
+                fn from(status: $register) -> Self {
+                    Self {
+                        xyz_overrun: status.xyz_overrun(),
+                        z_overrun: status.z_overrun(),
+                        y_overrun: status.y_overrun(),
+                        x_overrun: status.x_overrun(),
+                        xyz_available: status.xyz_available(),
+                        z_available: status.z_available(),
+                        y_available: status.y_available(),
+                        x_available: status.x_available(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+status_from_register!(StatusA, StatusM);
+
+/// Number of `wait_for_measurements` calls that found older samples overwritten. The
+/// chip only flags that an overrun happened, so several lost samples count once.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OverrunCount {
+    pub accelerometer: u32,
+    pub magnetometer: u32,
+}
+
+impl<T> LSM303D<T>
+where T: Transport {
+    pub fn read_accelerometer_status(&mut self) -> Result<Status, ()> {
+        Ok(self.transport.read::<StatusA>()?.into())
+    }
+
+    pub fn read_magnetometer_status(&mut self) -> Result<Status, ()> {
+        Ok(self.transport.read::<StatusM>()?.into())
+    }
+
+    pub fn is_accel_ready(&mut self) -> Result<bool, ()> {
+        Ok(self.read_accelerometer_status()?.xyz_available)
+    }
+
+    pub fn is_mag_ready(&mut self) -> Result<bool, ()> {
+        Ok(self.read_magnetometer_status()?.xyz_available)
+    }
+
+    /// Waits until both sensors have a new sample on all axes and returns it, polling
+    /// the status registers once per millisecond. Returns `Err` if no sample arrives
+    /// within `timeout_ms`, e.g. because a sensor is powered down.
+    pub fn wait_for_measurements<D: DelayMs<u16>>(&mut self, delay: &mut D, timeout_ms: u16) -> Result<Measurements, ()> {
+        let mut acc_ready = false;
+        let mut mag_ready = false;
+        let mut acc_overrun = false;
+        let mut mag_overrun = false;
+
+        let mut waited_ms = 0;
+        loop {
+            if !acc_ready {
+                let status = self.read_accelerometer_status()?;
+                acc_ready = status.xyz_available;
+                acc_overrun |= status.xyz_overrun;
+            }
+            if !mag_ready {
+                let status = self.read_magnetometer_status()?;
+                mag_ready = status.xyz_available;
+                mag_overrun |= status.xyz_overrun;
+            }
+            if acc_ready && mag_ready {
+                break;
+            }
+
+            if waited_ms == timeout_ms {
+                return Err(());
+            }
+            delay.delay_ms(1);
+            waited_ms += 1;
+        }
+
+        let measurements = self.read_measurements()?;
+
+        if acc_overrun {
+            self.overruns.accelerometer = self.overruns.accelerometer.saturating_add(1);
+        }
+        if mag_overrun {
+            self.overruns.magnetometer = self.overruns.magnetometer.saturating_add(1);
+        }
+
+        Ok(measurements)
+    }
+
+    pub fn overrun_count(&self) -> OverrunCount {
+        self.overruns
+    }
+
+    pub fn reset_overrun_count(&mut self) {
+        self.overruns = OverrunCount::default();
+    }
+}
@@ -0,0 +1,110 @@
+use embedded_hal::blocking::i2c::{WriteRead, Write};
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::register::RegisterAccess;
+
+/// Register level access to the chip, independent of the bus it is attached to.
+pub trait Transport {
+    /// Reads consecutive registers starting at `register`, auto-incrementing the address.
+    fn read_registers(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), ()>;
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), ()>;
+}
+
+impl<T: Transport> RegisterAccess for T {
+    fn read_raw(&mut self, address: u8) -> Result<u8, ()> {
+        let mut buffer = [0u8; 1];
+        self.read_registers(address, &mut buffer)?;
+
+        Ok(buffer[0])
+    }
+
+    fn write_raw(&mut self, address: u8, value: u8) -> Result<(), ()> {
+        self.write_register(address, value)
+    }
+}
+
+/// I2C address selected by the SA0 pin.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SlaveAddress {
+    SA0Low  = 0x1E,
+    SA0High = 0x1D,
+}
+
+pub struct I2cTransport<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cTransport<I2C> {
+    pub fn new(i2c: I2C, address: SlaveAddress) -> Self {
+        Self {
+            i2c,
+            address: address as u8,
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C> Transport for I2cTransport<I2C>
+where I2C: WriteRead + Write {
+    fn read_registers(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), ()> {
+        // MSB of the sub-address enables auto-increment
+        let register = if buffer.len() > 1 { register | 0x80 } else { register };
+        self.i2c.write_read(self.address, &[register], buffer).map_err(|_| ())
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), ()> {
+        self.i2c.write(self.address, &[register, value]).map_err(|_| ())
+    }
+}
+
+/// 4-wire SPI, mode 3. Chip select is driven by the transport.
+pub struct SpiTransport<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+}
+
+impl<SPI, CS> SpiTransport<SPI, CS>
+where CS: OutputPin {
+    pub fn new(spi: SPI, mut cs: CS) -> Result<Self, ()> {
+        cs.set_high().map_err(|_| ())?;
+
+        Ok(Self { spi, cs })
+    }
+
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI, CS> Transport for SpiTransport<SPI, CS>
+where
+    SPI: spi::Transfer<u8> + spi::Write<u8>,
+    CS: OutputPin,
+{
+    fn read_registers(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), ()> {
+        // Bit 7 selects read, bit 6 auto-increments the address
+        let command = if buffer.len() > 1 { register | 0xC0 } else { register | 0x80 };
+        buffer.fill(0);
+
+        self.cs.set_low().map_err(|_| ())?;
+        let result = self.spi.write(&[command]).map_err(|_| ())
+            .and_then(|_| self.spi.transfer(buffer).map(|_| ()).map_err(|_| ()));
+        self.cs.set_high().map_err(|_| ())?;
+
+        result
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), ()> {
+        self.cs.set_low().map_err(|_| ())?;
+        let result = self.spi.write(&[register & 0x3F, value]).map_err(|_| ());
+        self.cs.set_high().map_err(|_| ())?;
+
+        result
+    }
+}
@@ -0,0 +1,246 @@
+use libm::{cos, fabs, sin, sqrt};
+
+use super::{AccelerometerMeasurements, AccelerationDataRate};
+
+/// Number of spectral peaks reported per axis.
+pub const DOMINANT_FREQUENCIES: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpectralPeak {
+    /// Frequency in Hz, interpolated between FFT bins.
+    pub frequency: f64,
+    /// Amplitude of the sine component in g.
+    pub amplitude: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisVibration {
+    /// RMS of the acceleration with the static part (gravity) removed, in g.
+    pub rms: f64,
+    /// Largest deviation from the static part in g.
+    pub peak: f64,
+    /// Peak divided by RMS. Rises when short impacts, e.g. from bearing damage, appear.
+    pub crest_factor: f64,
+    /// Strongest spectral peaks, largest first.
+    pub dominant: [Option<SpectralPeak>; DOMINANT_FREQUENCIES],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VibrationReport {
+    pub x: AxisVibration,
+    pub y: AxisVibration,
+    pub z: AxisVibration,
+}
+
+/// Analyzes a block of consecutive samples taken at `data_rate`. The block length has to
+/// be a power of two, at least 4. The frequency resolution is data rate / block length.
+///
+/// Works on the stack only, the FFT buffers take 16 bytes per sample.
+pub fn analyze<const N: usize>(samples: &[AccelerometerMeasurements; N], data_rate: AccelerationDataRate) -> Result<VibrationReport, ()> {
+    let frequency = data_rate.frequency();
+    if frequency == 0.0 || N < 4 || !N.is_power_of_two() {
+        return Err(());
+    }
+
+    let mut real = [0.0; N];
+    let mut imaginary = [0.0; N];
+
+    let mut axis = |value: fn(&AccelerometerMeasurements) -> f64| {
+        for (real, sample) in real.iter_mut().zip(samples.iter()) {
+            *real = value(sample);
+        }
+        analyze_axis(&mut real, &mut imaginary, frequency)
+    };
+
+    Ok(VibrationReport {
+        x: axis(|sample| sample.x),
+        y: axis(|sample| sample.y),
+        z: axis(|sample| sample.z),
+    })
+}
+
+fn analyze_axis(real: &mut [f64], imaginary: &mut [f64], frequency: f64) -> AxisVibration {
+    let n = real.len();
+
+    let mean = real.iter().sum::<f64>() / n as f64;
+    let mut square_sum = 0.0;
+    let mut peak: f64 = 0.0;
+    for value in real.iter_mut() {
+        *value -= mean;
+        square_sum += *value * *value;
+        peak = peak.max(fabs(*value));
+    }
+    let rms = sqrt(square_sum / n as f64);
+
+    // Hann window, its coherent gain of 0.5 is undone when scaling the amplitudes
+    let step = 2.0 * core::f64::consts::PI / n as f64;
+    for (k, value) in real.iter_mut().enumerate() {
+        *value *= 0.5 - 0.5 * cos(step * k as f64);
+    }
+    imaginary.fill(0.0);
+
+    fft(real, imaginary);
+
+    // Single sided amplitude spectrum, reusing the real buffer
+    let scale = 4.0 / n as f64;
+    for k in 0..n / 2 {
+        real[k] = sqrt(real[k] * real[k] + imaginary[k] * imaginary[k]) * scale;
+    }
+
+    AxisVibration {
+        rms,
+        peak,
+        crest_factor: if rms > 0.0 { peak / rms } else { 0.0 },
+        dominant: dominant_peaks(&real[..n / 2], frequency / n as f64),
+    }
+}
+
+/// Picks the largest local maxima, skipping the DC bin.
+fn dominant_peaks(spectrum: &[f64], resolution: f64) -> [Option<SpectralPeak>; DOMINANT_FREQUENCIES] {
+    let mut peaks: [Option<SpectralPeak>; DOMINANT_FREQUENCIES] = [None; DOMINANT_FREQUENCIES];
+
+    for k in 1..spectrum.len() {
+        let left = spectrum[k - 1];
+        let center = spectrum[k];
+        let right = spectrum.get(k + 1).copied().unwrap_or(0.0);
+        if center <= left || center < right || center <= 0.0 {
+            continue;
+        }
+
+        // Parabolic interpolation between the neighbouring bins
+        let denominator = left - 2.0 * center + right;
+        let offset = if denominator != 0.0 { 0.5 * (left - right) / denominator } else { 0.0 };
+        let candidate = SpectralPeak {
+            frequency: (k as f64 + offset) * resolution,
+            amplitude: center - 0.25 * (left - right) * offset,
+        };
+
+        if let Some(position) = peaks.iter().position(|peak| peak.is_none_or(|peak| peak.amplitude < candidate.amplitude)) {
+            peaks.copy_within(position..DOMINANT_FREQUENCIES - 1, position + 1);
+            peaks[position] = Some(candidate);
+        }
+    }
+
+    peaks
+}
+
+/// In-place iterative radix-2 FFT. The length has to be a power of two.
+fn fft(real: &mut [f64], imaginary: &mut [f64]) {
+    let n = real.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * core::f64::consts::PI / length as f64;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (w_imaginary, w_real) = (sin(angle * k as f64), cos(angle * k as f64));
+                let a = start + k;
+                let b = a + length / 2;
+
+                let t_real = real[b] * w_real - imaginary[b] * w_imaginary;
+                let t_imaginary = real[b] * w_imaginary + imaginary[b] * w_real;
+                real[b] = real[a] - t_real;
+                imaginary[b] = imaginary[a] - t_imaginary;
+                real[a] += t_real;
+                imaginary[a] += t_imaginary;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::{PI, SQRT_2};
+
+    use super::*;
+
+    const N: usize = 1024;
+
+    /// One block at 1600 Hz, where the resolution is 1.5625 Hz per bin.
+    fn block(x: impl Fn(f64) -> f64, z: impl Fn(f64) -> f64) -> [AccelerometerMeasurements; N] {
+        let mut samples = [AccelerometerMeasurements::default(); N];
+        for (k, sample) in samples.iter_mut().enumerate() {
+            let time = k as f64 / 1600.0;
+            *sample = AccelerometerMeasurements { x: x(time), y: 0.25, z: z(time) };
+        }
+        samples
+    }
+
+    fn sine(amplitude: f64, frequency: f64, time: f64) -> f64 {
+        amplitude * sin(2.0 * PI * frequency * time)
+    }
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64) {
+        assert!(fabs(value - expected) <= tolerance, "{value} != {expected}");
+    }
+
+    #[test]
+    fn sine_with_offset() {
+        // 100 Hz falls on bin 64 and on the sine maxima, gravity is the offset on z
+        let samples = block(|_| 0.0, |time| 1.0 + sine(0.2, 100.0, time));
+        let z = analyze(&samples, AccelerationDataRate::Hz1600).unwrap().z;
+
+        assert_close(z.rms, 0.2 / SQRT_2, 1e-9);
+        assert_close(z.peak, 0.2, 1e-9);
+        assert_close(z.crest_factor, SQRT_2, 1e-9);
+
+        let dominant = z.dominant[0].unwrap();
+        assert_close(dominant.frequency, 100.0, 1e-9);
+        assert_close(dominant.amplitude, 0.2, 1e-9);
+    }
+
+    #[test]
+    fn interpolates_between_bins() {
+        // 110 Hz lies at bin 70.4
+        let samples = block(|time| sine(0.1, 110.0, time), |_| 1.0);
+        let x = analyze(&samples, AccelerationDataRate::Hz1600).unwrap().x;
+
+        let dominant = x.dominant[0].unwrap();
+        assert_close(dominant.frequency, 110.0, 0.1);
+        // The parabola leaves a few percent of the Hann scalloping loss between bins
+        assert_close(dominant.amplitude, 0.1, 0.006);
+    }
+
+    #[test]
+    fn orders_peaks_by_amplitude() {
+        let samples = block(|time| sine(0.05, 250.0, time) + sine(0.2, 100.0, time), |_| 1.0);
+        let report = analyze(&samples, AccelerationDataRate::Hz1600).unwrap();
+
+        let [first, second, _] = report.x.dominant;
+        assert_close(first.unwrap().frequency, 100.0, 1e-6);
+        assert_close(second.unwrap().frequency, 250.0, 1e-6);
+        assert_close(second.unwrap().amplitude, 0.05, 1e-6);
+
+        // Constant axes have no vibration at all
+        assert_eq!(report.y.rms, 0.0);
+        assert_eq!(report.y.crest_factor, 0.0);
+        assert_eq!(report.y.dominant, [None; DOMINANT_FREQUENCIES]);
+    }
+
+    #[test]
+    fn rejects_invalid_blocks() {
+        let samples = [AccelerometerMeasurements::default(); 1000];
+        assert!(analyze(&samples, AccelerationDataRate::Hz1600).is_err());
+
+        let samples = [AccelerometerMeasurements::default(); 2];
+        assert!(analyze(&samples, AccelerationDataRate::Hz1600).is_err());
+
+        let samples = [AccelerometerMeasurements::default(); 16];
+        assert!(analyze(&samples, AccelerationDataRate::PowerOff).is_err());
+    }
+}
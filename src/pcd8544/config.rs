@@ -0,0 +1,72 @@
+use super::{Addressing, DisplayMode};
+
+/// Temperature coefficient of the LCD supply voltage, `Tc0` is the flattest curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TemperatureCoefficient {
+    Tc0 = 0x00,
+    Tc1 = 0x01,
+    Tc2 = 0x02,
+    Tc3 = 0x03,
+}
+
+/// Settings applied by `Pcd8544::configure`. Defaults match what `init` always used.
+///
+/// ```ignore
+/// let config = Pcd8544Config::new()
+///     .contrast(0x40)
+///     .display_mode(DisplayMode::Inverse);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Pcd8544Config {
+    pub(super) bias: u8,
+    pub(super) contrast: u8,
+    pub(super) temperature_coefficient: TemperatureCoefficient,
+    pub(super) display_mode: DisplayMode,
+    pub(super) addressing: Addressing,
+}
+
+impl Pcd8544Config {
+    pub fn new() -> Self {
+        Self {
+            bias: 0x04,
+            contrast: 0x3F,
+            temperature_coefficient: TemperatureCoefficient::Tc0,
+            display_mode: DisplayMode::Normal,
+            addressing: Addressing::HORIZONTAL,
+        }
+    }
+
+    /// Bias system, 0..=7. Checked when the configuration is applied.
+    pub fn bias(mut self, bias: u8) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    /// Operating voltage, 0..=0x7F. Checked when the configuration is applied.
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    pub fn temperature_coefficient(mut self, temperature_coefficient: TemperatureCoefficient) -> Self {
+        self.temperature_coefficient = temperature_coefficient;
+        self
+    }
+
+    pub fn display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    pub fn addressing(mut self, addressing: Addressing) -> Self {
+        self.addressing = addressing;
+        self
+    }
+}
+
+impl Default for Pcd8544Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,115 @@
+use core::fmt;
+
+use super::font::FONT;
+use super::graphics::{DisplayBuffer, BANKS, DISPLAY_WIDTH};
+
+/// Characters per line, each glyph is 5 pixels wide plus one pixel spacing.
+pub const COLUMNS: usize = DISPLAY_WIDTH / 6;
+pub const ROWS: usize = BANKS;
+
+/// Character terminal on top of a `DisplayBuffer`, usable with `write!`.
+///
+/// Text wraps at the end of the line and the content scrolls up once the last line is
+/// full. `\n` starts a new line, `\r` returns to the start of the current one.
+pub struct TextConsole<'a> {
+    buffer: &'a mut DisplayBuffer,
+    column: usize,
+    row: usize,
+}
+
+impl<'a> TextConsole<'a> {
+    /// Starts in the top left corner without touching the buffer content.
+    pub fn new(buffer: &'a mut DisplayBuffer) -> Self {
+        Self {
+            buffer,
+            column: 0,
+            row: 0,
+        }
+    }
+
+    pub fn buffer(&self) -> &DisplayBuffer {
+        self.buffer
+    }
+
+    /// Access for drawing or flushing while the console is in use.
+    pub fn buffer_mut(&mut self) -> &mut DisplayBuffer {
+        self.buffer
+    }
+
+    /// Column and row of the next character.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.column, self.row)
+    }
+
+    /// Moves the cursor, clamped to the grid.
+    pub fn set_cursor(&mut self, column: usize, row: usize) {
+        self.column = column.min(COLUMNS);
+        self.row = row.min(ROWS - 1);
+    }
+
+    /// Blanks the whole buffer and moves the cursor home.
+    pub fn clear(&mut self) {
+        self.buffer.data.fill(0);
+        self.buffer.mark_all_dirty();
+        self.column = 0;
+        self.row = 0;
+    }
+
+    /// Blanks from the cursor to the end of the line, e.g. after overwriting a value
+    /// with a shorter one.
+    pub fn clear_to_end_of_line(&mut self) {
+        for column in self.column..COLUMNS {
+            self.draw_cell(column, self.row, &[0; 5]);
+        }
+    }
+
+    pub fn put_char(&mut self, c: char) {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => self.column = 0,
+            _ => {
+                // Wrapping only when the next character arrives keeps a full line
+                // followed by `\n` from leaving an empty line behind
+                if self.column >= COLUMNS {
+                    self.new_line();
+                }
+                let glyph = if c.is_ascii() { &FONT[c as usize] } else { &FONT[0] };
+                self.draw_cell(self.column, self.row, glyph);
+                self.column += 1;
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < ROWS {
+            self.row += 1;
+        } else {
+            self.scroll_up();
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        let data = &mut self.buffer.data;
+        data.copy_within(DISPLAY_WIDTH.., 0);
+        data[(ROWS - 1) * DISPLAY_WIDTH..].fill(0);
+        self.buffer.mark_all_dirty();
+    }
+
+    fn draw_cell(&mut self, column: usize, row: usize, glyph: &[u8; 5]) {
+        let x = column * 6;
+        for (offset, value) in glyph.iter().chain(&[0]).enumerate() {
+            self.buffer.update_byte(x + offset, row, |_| *value);
+        }
+    }
+}
+
+impl fmt::Write for TextConsole<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put_char(c);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,571 @@
+use super::graphics::{DisplayBuffer, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+const WIDTH: i32 = DISPLAY_WIDTH as i32;
+const HEIGHT: i32 = DISPLAY_HEIGHT as i32;
+
+/// How a drawing operation changes the pixels it covers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Color {
+    /// Dark pixel, same as `put_pixel`.
+    On,
+    /// Light pixel, same as `clear_pixel`.
+    Off,
+    /// Inverts the pixel.
+    Xor,
+}
+
+impl Color {
+    fn apply(&self, byte: u8, mask: u8) -> u8 {
+        match self {
+            Color::On => byte | mask,
+            Color::Off => byte & !mask,
+            Color::Xor => byte ^ mask,
+        }
+    }
+}
+
+/// Drawing primitives. Coordinates are signed so shapes may extend past the display,
+/// everything outside of the 84x48 area is clipped. Widths and heights that are not
+/// positive draw nothing.
+impl DisplayBuffer {
+    pub fn pixel(&self, x: i32, y: i32) -> bool {
+        if !(0..WIDTH).contains(&x) || !(0..HEIGHT).contains(&y) {
+            return false;
+        }
+
+        self.data[index(x, y / 8)] & 1 << (y % 8) != 0
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if !(0..WIDTH).contains(&x) || !(0..HEIGHT).contains(&y) {
+            return;
+        }
+
+        self.update_byte(x as usize, (y / 8) as usize, |byte| color.apply(byte, 1 << (y % 8)));
+    }
+
+    /// Line from `(x, y)` to the right. All pixels share one bank, so a single mask is
+    /// applied to consecutive bytes.
+    pub fn draw_hline(&mut self, x: i32, y: i32, width: i32, color: Color) {
+        if !(0..HEIGHT).contains(&y) {
+            return;
+        }
+        let (start, end) = (x.max(0), x.saturating_add(width).min(WIDTH));
+        if start >= end {
+            return;
+        }
+
+        let mask = 1 << (y % 8);
+        for x in start..end {
+            self.update_byte(x as usize, (y / 8) as usize, |byte| color.apply(byte, mask));
+        }
+    }
+
+    /// Line from `(x, y)` downwards, writing whole bytes for fully covered banks.
+    pub fn draw_vline(&mut self, x: i32, y: i32, height: i32, color: Color) {
+        if !(0..WIDTH).contains(&x) {
+            return;
+        }
+        let (start, end) = (y.max(0), y.saturating_add(height).min(HEIGHT));
+        if start >= end {
+            return;
+        }
+
+        let mut y = start;
+        while y < end {
+            let bank = y / 8;
+            let last = end.min((bank + 1) * 8);
+            // Bits y % 8 up to (last - 1) % 8
+            let mask = (0xFFu16 << (y % 8)) as u8 & (0xFFu16 >> (8 - (last - bank * 8))) as u8;
+            self.update_byte(x as usize, bank as usize, |byte| color.apply(byte, mask));
+            y = last;
+        }
+    }
+
+    /// Bresenham line including both end points. The line is clipped to the display
+    /// first, so far away end points cost nothing.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let (x0, y0, x1, y1) = match clip_line(x0, y0, x1, y1) {
+            Some(line) => line,
+            None => return,
+        };
+        if y0 == y1 {
+            return self.draw_hline(x0.min(x1), y0, (x1 - x0).abs() + 1, color);
+        }
+        if x0 == x1 {
+            return self.draw_vline(x0, y0.min(y1), (y1 - y0).abs() + 1, color);
+        }
+
+        walk_line(x0, y0, x1, y1, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Rectangle outline. Corners are drawn once, so `Color::Xor` inverts the outline.
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        self.draw_hline(x, y, width, color);
+        if height > 1 {
+            self.draw_hline(x, y.saturating_add(height - 1), width, color);
+        }
+        if height > 2 {
+            self.draw_vline(x, y.saturating_add(1), height - 2, color);
+            if width > 1 {
+                self.draw_vline(x.saturating_add(width - 1), y.saturating_add(1), height - 2, color);
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        for column in x.max(0)..x.saturating_add(width).min(WIDTH) {
+            self.draw_vline(column, y, height, color);
+        }
+    }
+
+    /// Inverts every pixel in the region.
+    pub fn invert_rect(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.fill_rect(x, y, width, height, Color::Xor);
+    }
+
+    /// Midpoint circle outline around `(x, y)`.
+    pub fn draw_circle(&mut self, x: i32, y: i32, radius: i32, color: Color) {
+        if radius < 0 {
+            return;
+        }
+        let (x, y, radius) = (i64::from(x), i64::from(y), i64::from(radius));
+        if x + radius < 0 || x - radius >= WIDTH.into() || y + radius < 0 || y - radius >= HEIGHT.into() {
+            return;
+        }
+
+        let (mut dx, mut dy) = (0, radius);
+        let mut error = 1 - radius;
+        while dx <= dy {
+            self.circle_points(x, y, dx, dy, color);
+            dx += 1;
+            if error < 0 {
+                error += 2 * dx + 1;
+            } else {
+                dy -= 1;
+                error += 2 * (dx - dy) + 1;
+            }
+        }
+    }
+
+    /// Filled circle around `(x, y)`, drawn as one horizontal span per display row.
+    pub fn fill_circle(&mut self, x: i32, y: i32, radius: i32, color: Color) {
+        if radius < 0 {
+            return;
+        }
+        let (x, y, radius) = (i64::from(x), i64::from(y), i64::from(radius));
+
+        for row in (y - radius).max(0)..=(y + radius).min(i64::from(HEIGHT) - 1) {
+            let dy = row - y;
+            // Widest span whose ends stay within half a pixel of the circle
+            let half_width = isqrt(radius * radius + radius - dy * dy);
+            let start = (x - half_width).clamp(-1, WIDTH.into());
+            let end = (x + half_width).clamp(-1, WIDTH.into());
+            self.draw_hline(start as i32, row as i32, (end - start + 1) as i32, color);
+        }
+    }
+
+    pub fn draw_triangle(&mut self, points: [(i32, i32); 3], color: Color) {
+        let [(x0, y0), (x1, y1), (x2, y2)] = points;
+        self.draw_line(x0, y0, x1, y1, color);
+        self.draw_line(x1, y1, x2, y2, color);
+        self.draw_line(x2, y2, x0, y0, color);
+    }
+
+    /// Filled triangle, drawn as one horizontal span per row. The spans are built from
+    /// the same clipped Bresenham lines as `draw_triangle`, so the fill covers the outline.
+    pub fn fill_triangle(&mut self, points: [(i32, i32); 3], color: Color) {
+        let mut spans = [(i32::MAX, i32::MIN); DISPLAY_HEIGHT];
+        for (index, &(x0, y0)) in points.iter().enumerate() {
+            let (x1, y1) = points[(index + 1) % 3];
+            add_edge(&mut spans, x0, y0, x1, y1);
+        }
+
+        for (y, &(start, end)) in spans.iter().enumerate() {
+            if start <= end {
+                self.draw_hline(start, y as i32, end - start + 1, color);
+            }
+        }
+    }
+
+    /// Fills the area connected to `(x, y)` that has the same state as that pixel.
+    /// `Color::Xor` inverts the area.
+    pub fn flood_fill(&mut self, x: i32, y: i32, color: Color) {
+        if !(0..WIDTH).contains(&x) || !(0..HEIGHT).contains(&y) {
+            return;
+        }
+        let target = self.pixel(x, y);
+        let fill = match color {
+            Color::On => true,
+            Color::Off => false,
+            Color::Xor => !target,
+        };
+        if fill == target {
+            return;
+        }
+
+        // Bitmap of the area found so far, in display layout. Sweeping forward and
+        // backward until nothing changes needs no stack, only one pass per turn of the area.
+        let mut area = DisplayBuffer::new();
+        area.set_pixel(x, y, Color::On);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for forward in [true, false] {
+                for step in 0..WIDTH * HEIGHT {
+                    let step = if forward { step } else { WIDTH * HEIGHT - 1 - step };
+                    let (x, y) = (step % WIDTH, step / WIDTH);
+                    if area.pixel(x, y) || self.pixel(x, y) != target {
+                        continue;
+                    }
+                    if area.pixel(x - 1, y) || area.pixel(x + 1, y) || area.pixel(x, y - 1) || area.pixel(x, y + 1) {
+                        area.set_pixel(x, y, Color::On);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let color = if fill { Color::On } else { Color::Off };
+        for (position, mask) in area.data.iter().enumerate() {
+            let (bank, x) = (position / DISPLAY_WIDTH, position % DISPLAY_WIDTH);
+            self.update_byte(x, bank, |byte| color.apply(byte, *mask));
+        }
+    }
+
+    /// Copies a 1 bit per pixel bitmap with its top left corner at `(x, y)`. Rows are
+    /// stored top to bottom, each starting on a new byte, most significant bit first.
+    /// Set bits are drawn dark, clear bits light unless `transparent` is set.
+    pub fn blit(&mut self, bitmap: &[u8], width: usize, x: i32, y: i32, transparent: bool) {
+        if width == 0 {
+            return;
+        }
+        let stride = width.div_ceil(8);
+
+        for (row, line) in bitmap.chunks_exact(stride).enumerate() {
+            for column in 0..width {
+                let set = line[column / 8] & 0x80 >> (column % 8) != 0;
+                if set || !transparent {
+                    let color = if set { Color::On } else { Color::Off };
+                    self.set_pixel(x + column as i32, y + row as i32, color);
+                }
+            }
+        }
+    }
+
+    fn circle_points(&mut self, x: i64, y: i64, dx: i64, dy: i64, color: Color) {
+        // Skip the points that coincide on the axes and diagonals, keeping Xor exact
+        self.plot(x + dx, y + dy, color);
+        if dy != 0 {
+            self.plot(x + dx, y - dy, color);
+        }
+        if dx != 0 {
+            self.plot(x - dx, y + dy, color);
+            self.plot(x - dx, y - dy, color);
+        }
+        if dx == dy {
+            return;
+        }
+        self.plot(x + dy, y + dx, color);
+        self.plot(x - dy, y + dx, color);
+        if dx != 0 {
+            self.plot(x + dy, y - dx, color);
+            self.plot(x - dy, y - dx, color);
+        }
+    }
+
+    fn plot(&mut self, x: i64, y: i64, color: Color) {
+        if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+            self.set_pixel(x, y, color);
+        }
+    }
+}
+
+fn index(x: i32, bank: i32) -> usize {
+    (bank * WIDTH + x) as usize
+}
+
+/// Largest integer whose square does not exceed `value`, which must not be negative.
+fn isqrt(value: i64) -> i64 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's iteration started above the root decreases until it reaches it
+    let mut root = value;
+    let mut next = (root + 1) / 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+
+    root
+}
+
+/// Cohen-Sutherland clipping of a line to the display. Returns `None` if the line
+/// misses the display, otherwise end points that lie on it.
+fn clip_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Option<(i32, i32, i32, i32)> {
+    const LEFT: u8 = 1;
+    const RIGHT: u8 = 2;
+    const TOP: u8 = 4;
+    const BOTTOM: u8 = 8;
+
+    let (max_x, max_y) = (i64::from(WIDTH) - 1, i64::from(HEIGHT) - 1);
+    let outcode = |x: i64, y: i64| {
+        let mut code = 0;
+        if x < 0 {
+            code |= LEFT;
+        } else if x > max_x {
+            code |= RIGHT;
+        }
+        if y < 0 {
+            code |= TOP;
+        } else if y > max_y {
+            code |= BOTTOM;
+        }
+        code
+    };
+
+    let (mut x0, mut y0, mut x1, mut y1) = (i64::from(x0), i64::from(y0), i64::from(x1), i64::from(y1));
+    loop {
+        let (code0, code1) = (outcode(x0, y0), outcode(x1, y1));
+        if code0 | code1 == 0 {
+            return Some((x0 as i32, y0 as i32, x1 as i32, y1 as i32));
+        }
+        if code0 & code1 != 0 {
+            return None;
+        }
+
+        // Move an outside end point onto the border it is beyond
+        let code = if code0 != 0 { code0 } else { code1 };
+        let (x, y) = if code & TOP != 0 {
+            (interpolate(x0, y0, x1, y1, 0), 0)
+        } else if code & BOTTOM != 0 {
+            (interpolate(x0, y0, x1, y1, max_y), max_y)
+        } else if code & LEFT != 0 {
+            (0, interpolate(y0, x0, y1, x1, 0))
+        } else {
+            (max_x, interpolate(y0, x0, y1, x1, max_x))
+        };
+        if code == code0 {
+            (x0, y0) = (x, y);
+        } else {
+            (x1, y1) = (x, y);
+        }
+    }
+}
+
+/// First coordinate of the line through `(a0, b0)` and `(a1, b1)` where the second one
+/// equals `b`, rounded to the nearest integer.
+fn interpolate(a0: i64, b0: i64, a1: i64, b1: i64, b: i64) -> i64 {
+    // Differences of i32 coordinates need 33 bits, so the product does not fit an i64
+    let mut numerator = i128::from(a1 - a0) * i128::from(b - b0);
+    let mut denominator = i128::from(b1 - b0);
+    if denominator < 0 {
+        numerator = -numerator;
+        denominator = -denominator;
+    }
+
+    a0 + (2 * numerator + denominator).div_euclid(2 * denominator) as i64
+}
+
+/// Calls `f` for every pixel of the Bresenham line, end points included.
+fn walk_line<F: FnMut(i32, i32)>(x0: i32, y0: i32, x1: i32, y1: i32, mut f: F) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let step_x = if x0 < x1 { 1 } else { -1 };
+    let step_y = if y0 < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+
+    loop {
+        f(x, y);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Widens the per row `spans` to cover the edge from `(x0, y0)` to `(x1, y1)`.
+fn add_edge(spans: &mut [(i32, i32); DISPLAY_HEIGHT], x0: i32, y0: i32, x1: i32, y1: i32) {
+    let mut include = |x: i32, y: i32| {
+        let span = &mut spans[y as usize];
+        *span = (span.0.min(x), span.1.max(x));
+    };
+
+    if let Some((x0, y0, x1, y1)) = clip_line(x0, y0, x1, y1) {
+        walk_line(x0, y0, x1, y1, &mut include);
+    }
+
+    // Rows where the edge is beside the display still bound the span, clamped to just
+    // past the border
+    if y0 != y1 {
+        let (x0, y0, x1, y1) = (i64::from(x0), i64::from(y0), i64::from(x1), i64::from(y1));
+        for y in y0.min(y1).max(0)..=y0.max(y1).min(i64::from(HEIGHT) - 1) {
+            let x = interpolate(x0, y0, x1, y1, y).clamp(-1, WIDTH.into());
+            include(x as i32, y as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(buffer: &DisplayBuffer) -> u32 {
+        buffer.data.iter().map(|byte| byte.count_ones()).sum()
+    }
+
+    /// Pseudo random coordinates from a linear congruential generator.
+    fn coordinates(seed: &mut u32, range: i32, offset: i32) -> (i32, i32) {
+        let mut next = || {
+            *seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (*seed >> 8) as i32 % range - offset
+        };
+        (next(), next())
+    }
+
+    #[test]
+    fn lines_with_extreme_coordinates() {
+        let extremes = [i32::MIN, i32::MIN + 1, -1_000_000, -1, 0, 40, 83, 84, 1_000_000, i32::MAX];
+        let mut buffer = DisplayBuffer::new();
+        for x0 in extremes {
+            for y0 in extremes {
+                buffer.draw_line(x0, y0, x0.wrapping_neg(), i32::MIN + 1, Color::Xor);
+                buffer.draw_line(x0, y0, 40, 20, Color::Xor);
+            }
+        }
+
+        let mut buffer = DisplayBuffer::new();
+        buffer.draw_line(i32::MIN, 0, i32::MAX, 1, Color::On);
+        assert!(count(&buffer) > 0);
+
+        // Clipping keeps the pixels of the unclipped line
+        let mut buffer = DisplayBuffer::new();
+        buffer.draw_line(-1_000_000, -1_000_000, 1_000_000, 1_000_000, Color::On);
+        assert_eq!(count(&buffer), 48);
+        assert!((0..48).all(|i| buffer.pixel(i, i)));
+
+        let mut buffer = DisplayBuffer::new();
+        buffer.draw_line(i32::MAX, 10, i32::MIN, 10, Color::On);
+        assert_eq!(count(&buffer), 84);
+        assert!((0..84).all(|x| buffer.pixel(x, 10)));
+
+        let mut buffer = DisplayBuffer::new();
+        buffer.draw_line(-10, -1, 100, -50, Color::On);
+        assert_eq!(count(&buffer), 0);
+    }
+
+    #[test]
+    fn fill_triangle_covers_outline() {
+        let mut seed = 1;
+        for round in 0..2000 {
+            let (range, offset) = if round % 2 == 0 { (84, 0) } else { (400, 150) };
+            let points = [
+                coordinates(&mut seed, range, offset),
+                coordinates(&mut seed, range, offset),
+                coordinates(&mut seed, range, offset),
+            ];
+
+            let mut fill = DisplayBuffer::new();
+            fill.fill_triangle(points, Color::On);
+            let mut outline = DisplayBuffer::new();
+            outline.draw_triangle(points, Color::On);
+
+            for (filled, drawn) in fill.data.iter().zip(outline.data.iter()) {
+                assert_eq!(drawn & !filled, 0, "{points:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_triangles() {
+        let mut buffer = DisplayBuffer::new();
+        buffer.fill_triangle([(10, 10); 3], Color::On);
+        assert_eq!(count(&buffer), 1);
+        assert!(buffer.pixel(10, 10));
+
+        // Collinear points fill exactly their line
+        let mut buffer = DisplayBuffer::new();
+        buffer.fill_triangle([(0, 0), (20, 20), (40, 40)], Color::On);
+        let mut line = DisplayBuffer::new();
+        line.draw_line(0, 0, 40, 40, Color::On);
+        assert_eq!(buffer.data, line.data);
+
+        let mut buffer = DisplayBuffer::new();
+        buffer.fill_triangle([(-5, 30), (50, 30), (100, 30)], Color::On);
+        assert_eq!(count(&buffer), 84);
+
+        let mut buffer = DisplayBuffer::new();
+        buffer.fill_triangle([(i32::MIN, i32::MIN), (i32::MAX, 20), (10, i32::MAX)], Color::On);
+        buffer.fill_triangle([(-100, 5), (-50, 40), (-70, 20)], Color::Xor);
+        assert!(count(&buffer) > 0);
+    }
+
+    #[test]
+    fn xor_outlines_touch_every_pixel_once() {
+        for radius in 0..30 {
+            let mut on = DisplayBuffer::new();
+            on.draw_circle(40, 20, radius, Color::On);
+            let mut xor = DisplayBuffer::new();
+            xor.draw_circle(40, 20, radius, Color::Xor);
+            assert_eq!(on.data, xor.data, "radius {radius}");
+
+            xor.draw_circle(40, 20, radius, Color::Xor);
+            assert_eq!(count(&xor), 0);
+
+            let mut on = DisplayBuffer::new();
+            on.fill_circle(40, 20, radius, Color::On);
+            let mut xor = DisplayBuffer::new();
+            xor.fill_circle(40, 20, radius, Color::Xor);
+            assert_eq!(on.data, xor.data, "radius {radius}");
+        }
+
+        for (width, height) in [(1, 1), (1, 5), (5, 1), (2, 2), (10, 3), (84, 48), (100, 60)] {
+            let mut on = DisplayBuffer::new();
+            on.draw_rect(-5, 2, width, height, Color::On);
+            let mut xor = DisplayBuffer::new();
+            xor.draw_rect(-5, 2, width, height, Color::Xor);
+            assert_eq!(on.data, xor.data, "{width}x{height}");
+        }
+    }
+
+    #[test]
+    fn circles_with_extreme_coordinates() {
+        let mut buffer = DisplayBuffer::new();
+        buffer.fill_circle(40, 20, i32::MAX, Color::On);
+        assert_eq!(count(&buffer), 84 * 48);
+
+        buffer.fill_circle(i32::MIN, i32::MAX, i32::MAX, Color::Xor);
+        buffer.draw_circle(i32::MAX, i32::MIN, i32::MAX, Color::Xor);
+        buffer.draw_rect(i32::MAX, i32::MAX, i32::MAX, i32::MAX, Color::Xor);
+    }
+
+    #[test]
+    fn integer_square_root() {
+        for value in 0..10_000 {
+            let root = isqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value, "{value}");
+        }
+        let large = i64::from(i32::MAX) * i64::from(i32::MAX) + i64::from(i32::MAX);
+        assert_eq!(isqrt(large), i64::from(i32::MAX));
+    }
+}
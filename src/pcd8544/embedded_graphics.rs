@@ -0,0 +1,41 @@
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::Pixel;
+
+use super::graphics::{DisplayBuffer, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+impl OriginDimensions for DisplayBuffer {
+    fn size(&self) -> Size {
+        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
+    }
+}
+
+/// `BinaryColor::On` is a dark pixel on the LCD.
+impl DrawTarget for DisplayBuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where I: IntoIterator<Item = Pixel<Self::Color>> {
+        for Pixel(point, color) in pixels {
+            // Negative coordinates wrap to large values and are clipped by the buffer
+            let (x, y) = (point.x as usize, point.y as usize);
+            match color {
+                BinaryColor::On => self.put_pixel(x, y),
+                BinaryColor::Off => self.clear_pixel(x, y),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.data.fill(if color.is_on() { 0xFF } else { 0x00 });
+        self.mark_all_dirty();
+
+        Ok(())
+    }
+}
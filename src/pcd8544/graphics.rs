@@ -1,15 +1,94 @@
+use core::ops::Range;
+
 use super::font::FONT;
 
-static DISPLAY_WIDTH: usize = 84;
-static DISPLAY_HEIGHT: usize = 48;
+pub(crate) static DISPLAY_WIDTH: usize = 84;
+pub(crate) static DISPLAY_HEIGHT: usize = 48;
+pub(crate) const BANKS: usize = 6;
 
 pub struct DisplayBuffer {
+    /// Direct writes are not tracked, call `mark_dirty` or `mark_all_dirty` afterwards.
     pub data: [u8; 504],
+    // Columns changed since the last flush, per bank of 8 rows
+    dirty: [Option<(usize, usize)>; BANKS],
 }
 
 impl DisplayBuffer {
+    /// Empty buffer. Everything is marked dirty, as the display content is unknown.
     pub fn new() -> Self {
-        DisplayBuffer { data: [0; 504] }
+        Self::from_data([0; 504])
+    }
+
+    pub fn from_data(data: [u8; 504]) -> Self {
+        DisplayBuffer {
+            data,
+            dirty: [Some((0, DISPLAY_WIDTH)); BANKS],
+        }
+    }
+
+    /// Marks columns `columns` of `bank` as changed.
+    pub fn mark_dirty(&mut self, bank: usize, columns: Range<usize>) {
+        if bank >= BANKS {
+            return;
+        }
+        let (start, end) = (columns.start, columns.end.min(DISPLAY_WIDTH));
+        if start >= end {
+            return;
+        }
+
+        self.dirty[bank] = Some(match self.dirty[bank] {
+            Some((dirty_start, dirty_end)) => (dirty_start.min(start), dirty_end.max(end)),
+            None => (start, end),
+        });
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = [Some((0, DISPLAY_WIDTH)); BANKS];
+    }
+
+    /// Columns of `bank` changed since it was last marked clean.
+    pub fn dirty_span(&self, bank: usize) -> Option<Range<usize>> {
+        self.dirty.get(bank).copied().flatten().map(|(start, end)| start..end)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(Option::is_some)
+    }
+
+    pub fn mark_clean(&mut self, bank: usize) {
+        if let Some(dirty) = self.dirty.get_mut(bank) {
+            *dirty = None;
+        }
+    }
+
+    /// The six bytes of column `x`, top bank first.
+    pub fn column(&self, x: usize) -> [u8; BANKS] {
+        core::array::from_fn(|bank| self.data[bank * DISPLAY_WIDTH + x])
+    }
+
+    pub fn set_column(&mut self, x: usize, column: [u8; BANKS]) {
+        if x >= DISPLAY_WIDTH {
+            return;
+        }
+
+        for (bank, value) in column.into_iter().enumerate() {
+            self.update_byte(x, bank, |_| value);
+        }
+    }
+
+    /// The buffer in column order, as expected by `Pcd8544::lcd_data` with vertical addressing.
+    pub fn to_vertical(&self) -> [u8; 504] {
+        core::array::from_fn(|position| self.data[(position % BANKS) * DISPLAY_WIDTH + position / BANKS])
+    }
+
+    /// Changes one byte, marking it dirty if its value changes.
+    pub(crate) fn update_byte(&mut self, x: usize, bank: usize, f: impl FnOnce(u8) -> u8) {
+        let byte = &mut self.data[bank * DISPLAY_WIDTH + x];
+        let value = f(*byte);
+        if value != *byte {
+            *byte = value;
+            self.mark_dirty(bank, x..x + 1);
+        }
     }
 
     pub fn put_pixel(&mut self, x: usize, y: usize) {
@@ -17,9 +96,7 @@ impl DisplayBuffer {
             return;
         }
 
-        let pos = (y / 8, y % 8);
-        let cur = &mut self.data[pos.0 * DISPLAY_WIDTH + x];
-        *cur = *cur | 1u8 << pos.1;
+        self.update_byte(x, y / 8, |byte| byte | 1u8 << (y % 8));
     }
 
     pub fn clear_pixel(&mut self, x: usize, y: usize) {
@@ -27,9 +104,7 @@ impl DisplayBuffer {
             return;
         }
 
-        let pos = (y / 8, y % 8);
-        let cur = &mut self.data[pos.0 * DISPLAY_WIDTH + x];
-        *cur = *cur & (!(1u8 << pos.1));
+        self.update_byte(x, y / 8, |byte| byte & !(1u8 << (y % 8)));
     }
 
     pub fn text_mode_put_text(&mut self, text: &str, x: usize, y: usize) {
@@ -41,10 +116,8 @@ impl DisplayBuffer {
 
         for (pos, ch) in str_iter {
             let buf_start = DISPLAY_WIDTH * y + 6 * x + pos * 6;
-            let chunk: &mut [u8; 5] = &mut self.data[
-                buf_start..buf_start + 5
-            ].try_into().unwrap();
-            *chunk = FONT[*ch as usize];
+            self.data[buf_start..buf_start + 5].copy_from_slice(&FONT[*ch as usize]);
+            self.mark_dirty(buf_start / DISPLAY_WIDTH, buf_start % DISPLAY_WIDTH..buf_start % DISPLAY_WIDTH + 5);
         }
     }
 }
\ No newline at end of file
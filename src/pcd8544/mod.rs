@@ -1,7 +1,18 @@
-use embedded_hal::{digital::v2::OutputPin, blocking::spi::Write};
+use embedded_hal::{digital::v2::OutputPin, blocking::{delay::DelayMs, spi::Write}};
 
+use graphics::{DisplayBuffer, BANKS, DISPLAY_WIDTH};
+
+pub mod config;
+pub mod console;
+pub mod draw;
 pub mod graphics;
 pub mod font;
+#[cfg(feature = "embedded-graphics")]
+mod embedded_graphics;
+
+pub use config::{Pcd8544Config, TemperatureCoefficient};
+
+const RESET_PULSE_MS: u16 = 1;
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
@@ -9,8 +20,17 @@ enum Instruction {
     Nop = 0x00,
     FunctionSet = 0x20,
     DisplayControl = 0x08,
+    SetYAddress = 0x40,
+    SetXAddress = 0x80,
+}
+
+/// Instructions available after `FunctionSet` with `ExtendedSet::EXTENDED`.
+#[derive(Debug, Clone, Copy)]
+#[repr(u8)]
+enum ExtendedInstruction {
     Contrast = 0x80,
     BiasMode = 0x10,
+    TemperatureControl = 0x04,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -22,12 +42,21 @@ enum ExtendedSet {
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
+enum PowerMode {
+    Active    = 0x00,
+    PowerDown = 0x04,
+}
+
+/// Direction the RAM address advances in after each data byte. Horizontal moves along
+/// a bank, which matches the `DisplayBuffer` layout; vertical moves down a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum Addressing {
-    HORIZONTAL = 0x02,
-    VERTICAL   = 0x00,
+    HORIZONTAL = 0x00,
+    VERTICAL   = 0x02,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum DisplayMode {
     Blank   = 0x00,
@@ -41,6 +70,8 @@ pub struct Pcd8544<SPI, CE, DC, RST> {
     lcd_rst: RST,
     lcd_ce: CE,
     lcd_dc: DC,
+    power_mode: PowerMode,
+    addressing: Addressing,
 }
 
 impl<SPI, CE, DC, RST> Pcd8544<SPI, CE, DC, RST>
@@ -50,6 +81,8 @@ where
     DC: OutputPin,
     RST: OutputPin,
 {
+    /// Takes the pins and leaves the controller deselected. Call `init` to reset and
+    /// set up the display.
     pub fn new(
         spi: SPI,
         lcd_ce: CE,
@@ -61,41 +94,125 @@ where
             lcd_ce,
             lcd_dc,
             lcd_rst,
+            power_mode: PowerMode::Active,
+            addressing: Addressing::HORIZONTAL,
         };
-        n.reset()?;
+        n.lcd_ce.set_high().map_err(|_| ())?;
+        n.lcd_rst.set_high().map_err(|_| ())?;
 
         Ok(n)
     }
 
-    pub fn reset(&mut self) -> Result<(), ()> {
+    /// Pulses RST, which clears all registers. The controller stays powered down until the
+    /// next function set, e.g. from `configure`. The pulse is far longer than the 100 ns
+    /// minimum, as delays have 1 ms resolution.
+    pub fn reset<D: DelayMs<u16>>(&mut self, delay: &mut D) -> Result<(), ()> {
         self.lcd_rst.set_low().map_err(|_| ())?;
+        delay.delay_ms(RESET_PULSE_MS);
         self.lcd_rst.set_high().map_err(|_| ())?;
+        delay.delay_ms(RESET_PULSE_MS);
+        self.power_mode = PowerMode::Active;
+        self.addressing = Addressing::HORIZONTAL;
 
         Ok(())
     }
 
-    pub fn init(&mut self) -> Result<(), ()> {
-        self.reset()?;
+    /// Resets the controller, applies the default configuration and clears the display
+    /// RAM. Has to be called within 30 ms after power-up, before that RAM and registers
+    /// are undefined.
+    pub fn init<D: DelayMs<u16>>(&mut self, delay: &mut D) -> Result<(), ()> {
+        self.init_with_config(delay, &Pcd8544Config::default())
+    }
 
-        self.lcd_cmd(Instruction::FunctionSet as u8 | ExtendedSet::EXTENDED as u8)?;
-        self.set_bias(0x04)?;
-        self.set_contrast(0x3f)?;
-        self.lcd_cmd(Instruction::FunctionSet as u8 | ExtendedSet::STANDARD as u8)?;
-        self.lcd_cmd(Instruction::DisplayControl as u8 | DisplayMode::Normal as u8)?;
+    pub fn init_with_config<D: DelayMs<u16>>(&mut self, delay: &mut D, config: &Pcd8544Config) -> Result<(), ()> {
+        self.reset(delay)?;
+        self.configure(config)?;
+        self.clear()
+    }
+
+    /// Zeroes the display RAM. Does not touch any `DisplayBuffer`.
+    pub fn clear(&mut self) -> Result<(), ()> {
+        self.set_address(0, 0)?;
+        self.write_data(&[0u8; 504])
+    }
+
+    /// Applies every setting of `config`. Fails without sending anything if a value is
+    /// out of range.
+    pub fn configure(&mut self, config: &Pcd8544Config) -> Result<(), ()> {
+        if config.bias > 0x07 || config.contrast > 0x7F {
+            return Err(());
+        }
+
+        self.function_set(ExtendedSet::EXTENDED)?;
+        self.lcd_cmd(ExtendedInstruction::BiasMode as u8 | config.bias)?;
+        self.lcd_cmd(ExtendedInstruction::Contrast as u8 | config.contrast)?;
+        self.lcd_cmd(ExtendedInstruction::TemperatureControl as u8 | config.temperature_coefficient as u8)?;
+        self.addressing = config.addressing;
+        self.function_set(ExtendedSet::STANDARD)?;
+        self.set_display_mode(config.display_mode)?;
 
         Ok(())
     }
 
+    pub fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), ()> {
+        self.lcd_cmd(Instruction::DisplayControl as u8 | mode as u8)
+    }
+
+    /// Sets the LCD operating voltage, 0..=0x7F.
     pub fn set_contrast(&mut self, contrast: u8) -> Result<(), ()> {
-        self.lcd_cmd(Instruction::Contrast as u8 | contrast).map_err(|_| ())?;
+        if contrast > 0x7F {
+            return Err(());
+        }
 
-        Ok(())
+        self.extended_cmd(ExtendedInstruction::Contrast as u8 | contrast)
     }
 
-    fn set_bias(&mut self, bias: u8) -> Result<(), ()> {
-        self.lcd_cmd(Instruction::BiasMode as u8| bias).map_err(|_| ())?;
+    /// Sets the bias system, 0..=7.
+    pub fn set_bias(&mut self, bias: u8) -> Result<(), ()> {
+        if bias > 0x07 {
+            return Err(());
+        }
 
-        Ok(())
+        self.extended_cmd(ExtendedInstruction::BiasMode as u8 | bias)
+    }
+
+    pub fn set_temperature_coefficient(&mut self, coefficient: TemperatureCoefficient) -> Result<(), ()> {
+        self.extended_cmd(ExtendedInstruction::TemperatureControl as u8 | coefficient as u8)
+    }
+
+    /// Turns off the oscillator and LCD voltage generator. RAM keeps its content and can
+    /// still be written.
+    pub fn power_down(&mut self) -> Result<(), ()> {
+        self.power_mode = PowerMode::PowerDown;
+        self.function_set(ExtendedSet::STANDARD)
+    }
+
+    pub fn wake(&mut self) -> Result<(), ()> {
+        self.power_mode = PowerMode::Active;
+        self.function_set(ExtendedSet::STANDARD)
+    }
+
+    /// Selects the layout expected by `lcd_data` and `write_data`.
+    pub fn set_addressing(&mut self, addressing: Addressing) -> Result<(), ()> {
+        self.addressing = addressing;
+        self.function_set(ExtendedSet::STANDARD)
+    }
+
+    pub fn addressing(&self) -> Addressing {
+        self.addressing
+    }
+
+    fn function_set(&mut self, instructions: ExtendedSet) -> Result<(), ()> {
+        self.lcd_cmd(
+            Instruction::FunctionSet as u8 | self.power_mode as u8 | self.addressing as u8 | instructions as u8
+        )
+    }
+
+    /// Sends one instruction of the extended set and returns to the basic set.
+    fn extended_cmd(&mut self, cmd: u8) -> Result<(), ()> {
+        self.function_set(ExtendedSet::EXTENDED)?;
+        self.lcd_cmd(cmd)?;
+        self.function_set(ExtendedSet::STANDARD)
     }
 
     fn lcd_cmd(&mut self, cmd: u8) -> Result<(), ()>
@@ -109,8 +226,72 @@ where
         Ok(())
     }
 
+    /// Sends a whole frame, starting at the top left corner. The layout has to match the
+    /// addressing mode, see `DisplayBuffer::to_vertical` for vertical addressing.
     pub fn lcd_data(&mut self, data: &mut [u8; 504]) -> Result<(), ()>
     {
+        self.set_address(0, 0)?;
+        self.write_data(data)
+    }
+
+    /// Sends only the parts of `buffer` changed since the last flush.
+    pub fn flush(&mut self, buffer: &mut DisplayBuffer) -> Result<(), ()> {
+        if self.addressing == Addressing::VERTICAL {
+            return self.flush_columns(buffer);
+        }
+
+        for bank in 0..BANKS {
+            if let Some(columns) = buffer.dirty_span(bank) {
+                self.set_address(columns.start as u8, bank as u8)?;
+                let offset = bank * DISPLAY_WIDTH;
+                self.write_data(&buffer.data[offset + columns.start..offset + columns.end])?;
+                buffer.mark_clean(bank);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Vertical addressing wraps to the top of the next column, so the changed columns
+    /// are sent in full.
+    fn flush_columns(&mut self, buffer: &mut DisplayBuffer) -> Result<(), ()> {
+        let columns = (0..BANKS)
+            .filter_map(|bank| buffer.dirty_span(bank))
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end));
+        let Some(columns) = columns else {
+            return Ok(());
+        };
+
+        let mut data = [0u8; 504];
+        let length = columns.len() * BANKS;
+        for (chunk, x) in data[..length].chunks_exact_mut(BANKS).zip(columns.clone()) {
+            chunk.copy_from_slice(&buffer.column(x));
+        }
+
+        self.set_address(columns.start as u8, 0)?;
+        self.write_data(&data[..length])?;
+        for bank in 0..BANKS {
+            buffer.mark_clean(bank);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the RAM address to column `x` (0..84) of `bank` (0..6), a row of 8 pixels.
+    /// Must be called in the basic instruction set.
+    pub fn set_address(&mut self, x: u8, bank: u8) -> Result<(), ()> {
+        if usize::from(x) >= DISPLAY_WIDTH || usize::from(bank) >= BANKS {
+            return Err(());
+        }
+
+        self.lcd_cmd(Instruction::SetXAddress as u8 | x)?;
+        self.lcd_cmd(Instruction::SetYAddress as u8 | bank)?;
+
+        Ok(())
+    }
+
+    /// Writes raw bytes at the current RAM address, which advances after each byte.
+    pub fn write_data(&mut self, data: &[u8]) -> Result<(), ()> {
         self.lcd_dc.set_high().map_err(|_| ())?;
         self.lcd_ce.set_low().map_err(|_| ())?;
         self.spi.write(data).map_err(|_| ())?;
@@ -0,0 +1,126 @@
+//! Typed 8 bit device registers.
+//!
+//! Drivers describe each register once with the `register!` macro and then access it
+//! through `RegisterAccess`, instead of shifting and masking raw bytes.
+
+/// An 8 bit register at a fixed address.
+pub trait Register: Copy {
+    const ADDRESS: u8;
+    /// Value after power-on or reboot.
+    const RESET: u8;
+
+    fn from_bits(bits: u8) -> Self;
+    fn bits(&self) -> u8;
+
+    fn reset() -> Self {
+        Self::from_bits(Self::RESET)
+    }
+}
+
+/// A value stored in a multi-bit field. Values are kept in their register position,
+/// so `bits` is already shifted into place.
+pub trait Field: Sized {
+    fn from_bits(bits: u8) -> Option<Self>;
+    fn bits(&self) -> u8;
+}
+
+impl Field for u8 {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(bits)
+    }
+
+    fn bits(&self) -> u8 {
+        *self
+    }
+}
+
+/// Read/modify/write of typed registers on top of raw byte access.
+pub trait RegisterAccess {
+    fn read_raw(&mut self, address: u8) -> Result<u8, ()>;
+    fn write_raw(&mut self, address: u8, value: u8) -> Result<(), ()>;
+
+    fn read<R: Register>(&mut self) -> Result<R, ()> {
+        Ok(R::from_bits(self.read_raw(R::ADDRESS)?))
+    }
+
+    fn write<R: Register>(&mut self, register: R) -> Result<(), ()> {
+        self.write_raw(R::ADDRESS, register.bits())
+    }
+
+    /// Reads the register, lets `f` change it and writes it back. Returns the written value.
+    fn modify<R: Register, F: FnOnce(&mut R)>(&mut self, f: F) -> Result<R, ()> {
+        let mut register = self.read::<R>()?;
+        f(&mut register);
+        self.write(register)?;
+
+        Ok(register)
+    }
+}
+
+/// Defines a register type with accessors for its flags and fields.
+///
+/// ```ignore
+/// register! {
+///     Ctrl1 = 0x20, reset 0x07 {
+///         flag axis_x, set_axis_x: 0;
+///         field data_rate, set_data_rate: AccelerationDataRate = 0b1111_0000;
+///     }
+/// }
+/// ```
+macro_rules! register {
+    (
+        $(#[$meta:meta])*
+        $name:ident = $address:literal, reset $reset:literal {
+            $( flag $flag:ident, $set_flag:ident: $bit:literal; )*
+            $( field $field:ident, $set_field:ident: $ty:ty = $mask:literal; )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct $name(u8);
+
+        impl $crate::register::Register for $name {
+            const ADDRESS: u8 = $address;
+            const RESET: u8 = $reset;
+
+            fn from_bits(bits: u8) -> Self {
+                Self(bits)
+            }
+
+            fn bits(&self) -> u8 {
+                self.0
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            $(
+                pub fn $flag(&self) -> bool {
+                    self.0 & (1 << $bit) != 0
+                }
+
+                pub fn $set_flag(&mut self, value: bool) -> &mut Self {
+                    if value {
+                        self.0 |= 1 << $bit;
+                    } else {
+                        self.0 &= !(1 << $bit);
+                    }
+                    self
+                }
+            )*
+            $(
+                /// `None` if the field holds a reserved value.
+                pub fn $field(&self) -> Option<$ty> {
+                    <$ty as $crate::register::Field>::from_bits(self.0 & $mask)
+                }
+
+                pub fn $set_field(&mut self, value: $ty) -> &mut Self {
+                    self.0 = (self.0 & !$mask) | ($crate::register::Field::bits(&value) & $mask);
+                    self
+                }
+            )*
+        }
+    };
+}
+
+pub(crate) use register;